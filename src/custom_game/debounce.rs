@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use valence::prelude::*;
+
+/// inputs of the same kind for the same player and cell within this many ticks are treated as duplicates
+const DEBOUNCE_TICKS: i64 = 4;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum ClickKind {
+    Left,
+    /// off hand interactions are dropped before they reach the debouncer, so this is always the main hand
+    Right,
+    /// not a click on a block, but recorded alongside them so replays get the same hints
+    Hint,
+    /// the player walked onto a block
+    Step,
+}
+
+/// Drops duplicate interact packets, while letting fast inputs on different cells through
+#[derive(Resource, Default)]
+pub struct InputDebouncer {
+    last_inputs: HashMap<(Entity, BlockPos, ClickKind), i64>,
+}
+
+impl InputDebouncer {
+    /// returns true if the input is not a duplicate and should be passed on to the games
+    pub fn accept(&mut self, player: Entity, pos: BlockPos, kind: ClickKind, tick: i64) -> bool {
+        let key = (player, pos, kind);
+        if self
+            .last_inputs
+            .get(&key)
+            .is_some_and(|last| tick - last < DEBOUNCE_TICKS)
+        {
+            return false;
+        }
+        self.last_inputs.insert(key, tick);
+        return true;
+    }
+
+    fn forget_old_inputs(&mut self, tick: i64) {
+        self.last_inputs
+            .retain(|_, last| tick - *last < DEBOUNCE_TICKS);
+    }
+}
+
+pub fn clear_old_inputs(mut debouncer: ResMut<InputDebouncer>, server: Res<Server>) {
+    debouncer.forget_old_inputs(server.current_tick());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates_within_the_window_are_dropped() {
+        let mut debouncer = InputDebouncer::default();
+        let player = Entity::from_raw(1);
+        let pos = BlockPos::new(0, 64, 0);
+        assert!(debouncer.accept(player, pos, ClickKind::Right, 0));
+        assert!(!debouncer.accept(player, pos, ClickKind::Right, DEBOUNCE_TICKS - 1));
+        assert!(debouncer.accept(player, pos, ClickKind::Right, DEBOUNCE_TICKS));
+    }
+
+    #[test]
+    fn other_inputs_pass() {
+        let mut debouncer = InputDebouncer::default();
+        let player = Entity::from_raw(1);
+        let pos = BlockPos::new(0, 64, 0);
+        assert!(debouncer.accept(player, pos, ClickKind::Right, 0));
+        assert!(debouncer.accept(player, pos, ClickKind::Left, 0));
+        assert!(debouncer.accept(player, BlockPos::new(1, 64, 0), ClickKind::Right, 0));
+        assert!(debouncer.accept(Entity::from_raw(2), pos, ClickKind::Right, 0));
+    }
+
+    #[test]
+    fn old_inputs_are_forgotten() {
+        let mut debouncer = InputDebouncer::default();
+        let pos = BlockPos::new(0, 64, 0);
+        debouncer.accept(Entity::from_raw(1), pos, ClickKind::Left, 0);
+        debouncer.forget_old_inputs(DEBOUNCE_TICKS);
+        assert!(debouncer.last_inputs.is_empty());
+    }
+}
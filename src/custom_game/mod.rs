@@ -0,0 +1,409 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    ops::{Deref, DerefMut},
+};
+
+mod debounce;
+mod participants;
+mod replay;
+
+use crate::{postgres_wrapper::PostgresWrapper, seed::GameSeed};
+use debounce::{clear_old_inputs, ClickKind, InputDebouncer};
+use replay::play_replays;
+use valence::{interact_block::InteractBlockEvent, prelude::*, title::SetTitle};
+
+pub use participants::Participants;
+pub use replay::{GameRecording, ReplayPlayback};
+
+pub struct CustomGamePlugin;
+
+impl Plugin for CustomGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputDebouncer>()
+            .add_event::<HintEvent>()
+            .add_systems(
+                Update,
+                (
+                    clear_old_inputs,
+                    // inputs are recorded with the amount of ticks before them, so they are
+                    // always handled before ticking to make replays play out the same
+                    (
+                        on_block_click,
+                        on_block_break,
+                        on_hint,
+                        on_player_move,
+                        tick_games,
+                    )
+                        .chain(),
+                    despawn_games,
+                    build_spawned_games,
+                    play_replays.after(build_spawned_games),
+                    send_announcements,
+                ),
+            );
+    }
+}
+
+fn build_spawned_games(
+    mut layer: Query<&mut ChunkLayer>,
+    mut new_games: Query<(Entity, &mut CustomGameContainer), Added<CustomGameContainer>>,
+    mut clients: Query<&mut Client, &UniqueId>,
+    mut commands: Commands,
+) {
+    for mut new_game in new_games.iter_mut() {
+        if let Err(err) = new_game.1.build_blocks(layer.single_mut().as_mut()) {
+            match clients.get_mut(new_game.1.participants().owner().0) {
+                Ok(mut player) => {
+                    player.send_chat_message(format!("Couldnt start game: {err}"));
+                    commands.entity(new_game.0).despawn();
+                }
+                Err(err) => tracing::warn!("A games, player couldnt be found: {}", err),
+            }
+        }
+    }
+}
+
+fn on_block_click(
+    mut block_interacts: EventReader<InteractBlockEvent>,
+    mut games: Query<
+        (&mut CustomGameContainer, Option<&mut GameRecording>),
+        (Without<ReplayPlayback>, Without<InputLock>),
+    >,
+    mut layer: Query<&mut ChunkLayer>,
+    mut debouncer: ResMut<InputDebouncer>,
+    server: Res<Server>,
+) {
+    for interaction in block_interacts.iter() {
+        // the client sends an additional off hand interaction for every click on a block
+        if interaction.hand != Hand::Main {
+            continue;
+        }
+        if !debouncer.accept(
+            interaction.client,
+            interaction.position,
+            ClickKind::Right,
+            server.current_tick(),
+        ) {
+            continue;
+        }
+        games.for_each_mut(|(rsg, recording)| {
+            if rsg.participants().can_interact(interaction.client) {
+                let origin = rsg.origin();
+                let accepted = rsg.into_inner().0.click_right(
+                    &interaction.position,
+                    interaction.client,
+                    layer.single_mut().into_inner(),
+                );
+                if accepted {
+                    if let Some(mut recording) = recording {
+                        recording.record(ClickKind::Right, &interaction.position, &origin);
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn on_block_break(
+    mut block_interacts: EventReader<DiggingEvent>,
+    mut games: Query<
+        (&mut CustomGameContainer, Option<&mut GameRecording>),
+        (Without<ReplayPlayback>, Without<InputLock>),
+    >,
+    mut layer: Query<&mut ChunkLayer>,
+    mut debouncer: ResMut<InputDebouncer>,
+    server: Res<Server>,
+) {
+    for interaction in block_interacts.iter() {
+        if !debouncer.accept(
+            interaction.client,
+            interaction.position,
+            ClickKind::Left,
+            server.current_tick(),
+        ) {
+            continue;
+        }
+        games.for_each_mut(|(rsg, recording)| {
+            if rsg.participants().can_interact(interaction.client) {
+                let origin = rsg.origin();
+                let accepted = rsg.into_inner().0.click_left(
+                    &interaction.position,
+                    interaction.client,
+                    layer.single_mut().into_inner(),
+                );
+                if accepted {
+                    if let Some(mut recording) = recording {
+                        recording.record(ClickKind::Left, &interaction.position, &origin);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// a player asks the games they play in for a hint
+#[derive(Event)]
+pub struct HintEvent {
+    pub player: Entity,
+}
+
+fn on_hint(
+    mut hints: EventReader<HintEvent>,
+    mut games: Query<
+        (&mut CustomGameContainer, Option<&mut GameRecording>),
+        (
+            Without<ReplayPlayback>,
+            Without<InputLock>,
+            Without<HintLock>,
+        ),
+    >,
+    mut layer: Query<&mut ChunkLayer>,
+    mut clients: Query<&mut Client>,
+) {
+    for hint in hints.iter() {
+        let mut hinted = false;
+        games.for_each_mut(|(mut game, recording)| {
+            if game.participants().can_interact(hint.player)
+                && game.hint(hint.player, layer.single_mut().into_inner())
+            {
+                hinted = true;
+                if let Some(mut recording) = recording {
+                    let origin = game.origin();
+                    recording.record(ClickKind::Hint, &origin, &origin);
+                }
+            }
+        });
+        if !hinted {
+            if let Ok(mut client) = clients.get_mut(hint.player) {
+                client.send_chat_message("None of your games can give you a hint right now");
+            }
+        }
+    }
+}
+
+/// tells the games when a player walks onto another block, for games played by moving around
+fn on_player_move(
+    players: Query<(Entity, Ref<Position>), With<Client>>,
+    mut games: Query<
+        (&mut CustomGameContainer, Option<&mut GameRecording>),
+        (Without<ReplayPlayback>, Without<InputLock>),
+    >,
+    mut layer: Query<&mut ChunkLayer>,
+    mut standing_on: Local<HashMap<Entity, BlockPos>>,
+) {
+    standing_on.retain(|player, _| players.contains(*player));
+    for (player, pos) in players.iter() {
+        if !pos.is_changed() {
+            continue;
+        }
+        // the block below the feet of the player
+        let block = BlockPos::new(
+            pos.0.x.floor() as i32,
+            (pos.0.y - 0.1).floor() as i32,
+            pos.0.z.floor() as i32,
+        );
+        if standing_on.insert(player, block) == Some(block) {
+            continue;
+        }
+        games.for_each_mut(|(mut game, recording)| {
+            if game.participants().can_interact(player)
+                && game.step_on(&block, player, layer.single_mut().into_inner())
+            {
+                if let Some(mut recording) = recording {
+                    recording.record(ClickKind::Step, &block, &game.origin());
+                }
+            }
+        });
+    }
+}
+
+fn tick_games(
+    mut games: Query<
+        (&mut CustomGameContainer, Option<&mut GameRecording>),
+        Without<ReplayPlayback>,
+    >,
+    mut layer: Query<&mut ChunkLayer>,
+) {
+    games.for_each_mut(|(mut g, recording)| {
+        g.tick(layer.single_mut().into_inner());
+        if let Some(mut recording) = recording {
+            recording.tick();
+        }
+    });
+}
+
+fn send_announcements(
+    mut games: Query<&mut CustomGameContainer>,
+    mut clients: Query<(&mut Client, &Username)>,
+) {
+    for mut game in games.iter_mut() {
+        let announcements: Vec<String> = game
+            .participants_mut()
+            .take_announcements()
+            .into_iter()
+            .map(|(about, msg)| match about {
+                Some(player) => {
+                    let name = clients
+                        .get(player)
+                        .map_or("Someone".to_string(), |c| c.1 .0.clone());
+                    format!("{name} {msg}")
+                }
+                None => msg,
+            })
+            .collect();
+        let status = game.participants_mut().take_status();
+        if announcements.is_empty() && status.is_none() {
+            continue;
+        }
+        for (player, _) in game.participants().everyone() {
+            if let Ok((mut client, _)) = clients.get_mut(player) {
+                for msg in &announcements {
+                    client.send_chat_message(msg.clone());
+                }
+                if let Some(status) = &status {
+                    client.set_action_bar(status.clone());
+                }
+            }
+        }
+    }
+}
+
+fn despawn_games(
+    mut games: Query<(
+        Entity,
+        &mut CustomGameContainer,
+        Option<&GameRecording>,
+        Option<&ReplayPlayback>,
+    )>,
+    mut layer: Query<&mut ChunkLayer>,
+    mut clients: Query<&mut Client>,
+    mut commands: Commands,
+    mut database: Local<PostgresWrapper>,
+) {
+    games.for_each_mut(|g| {
+        if g.1.should_despawn() {
+            g.1.reset(layer.single_mut().into_inner());
+            // replays are only watched, their results arent saved again
+            if g.3.is_none() {
+                g.1.save(database.deref_mut());
+            }
+            if let Some(recording) = g.2.filter(|r| !r.inputs().is_empty()) {
+                let id = recording.encode().and_then(|data| {
+                    database.insert_replay(
+                        &recording.game,
+                        &recording.seed,
+                        &data,
+                        g.1.participants().owner().1,
+                    )
+                });
+                if let Some(id) = id {
+                    for (player, _) in g.1.participants().players() {
+                        if let Ok(mut client) = clients.get_mut(player) {
+                            client.send_chat_message(format!(
+                                "Replay saved, watch it with !replay {id} [speed]"
+                            ));
+                        }
+                    }
+                }
+            }
+            commands.entity(g.0).despawn()
+        }
+    });
+}
+
+/// games with this component dont receive any inputs, e.g. during the countdown of a race
+#[derive(Component)]
+pub struct InputLock;
+
+/// games with this component dont give hints, e.g. the boards of a race
+#[derive(Component)]
+pub struct HintLock;
+
+#[derive(Component)]
+pub struct CustomGameContainer(pub Box<dyn CustomGame + Send + Sync>);
+
+impl Deref for CustomGameContainer {
+    type Target = Box<dyn CustomGame + Send + Sync>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for CustomGameContainer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+pub trait CustomGame {
+    fn build_blocks(&mut self, layer: &mut ChunkLayer) -> Result<(), BuildGameError>;
+
+    fn tick(&mut self, layer: &mut ChunkLayer);
+
+    /// returns false if the click didnt hit the game, so it isnt recorded
+    fn click_right(&mut self, click_pos: &BlockPos, player: Entity, layer: &mut ChunkLayer)
+        -> bool;
+    /// returns false if the click didnt hit the game, so it isnt recorded
+    fn click_left(&mut self, click_pos: &BlockPos, player: Entity, layer: &mut ChunkLayer) -> bool;
+    /// a player walked onto a block, pos is the block below their feet.
+    /// Returns false if the game isnt played by walking around, so the step isnt recorded
+    fn step_on(&mut self, _pos: &BlockPos, _player: Entity, _layer: &mut ChunkLayer) -> bool {
+        return false;
+    }
+    /// helps a player who is stuck, returns false if the game doesnt give hints right now
+    fn hint(&mut self, _player: Entity, _layer: &mut ChunkLayer) -> bool {
+        return false;
+    }
+
+    /// the next move of an automatic solver, for games which can play themselves
+    fn solver_move(&mut self) -> Option<SolverMove> {
+        return None;
+    }
+
+    /// the current state of the game as text, for games which can be saved and loaded again
+    fn export_text(&self) -> Option<String> {
+        return None;
+    }
+
+    /// removes the blocks of the game
+    fn reset(&self, layer: &mut ChunkLayer);
+    /// saves the result of the game, called when it despawns
+    fn save(&self, pgsql: &mut PostgresWrapper);
+
+    fn should_despawn(&self) -> bool;
+
+    /// how much of the game has been completed, from 0.0 to 1.0
+    fn progress(&self) -> f32;
+    fn is_over(&self) -> bool;
+    fn is_won(&self) -> bool;
+
+    /// the seed the game was generated from, games with the same seed and inputs play out the same
+    fn seed(&self) -> GameSeed;
+    /// a fixed block of the game, recorded inputs are stored relative to it
+    fn origin(&self) -> BlockPos;
+
+    fn participants(&self) -> &Participants;
+    fn participants_mut(&mut self) -> &mut Participants;
+}
+
+pub struct SolverMove {
+    pub pos: BlockPos,
+    /// right click the block instead of left clicking it
+    pub flag: bool,
+    /// the solver couldnt deduce a move and took a guess
+    pub guess: bool,
+}
+
+#[derive(Debug)]
+pub enum BuildGameError {
+    BlocksInTheWay,
+}
+
+impl Display for BuildGameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildGameError::BlocksInTheWay => write!(f, "Error! There are Blocks in the Way"),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use valence::prelude::*;
+
+/// Everyone taking part in a game.
+/// The owner started the game, collaborators were invited by the owner and may interact with it,
+/// spectators can only watch.
+pub struct Participants {
+    owner: (Entity, UniqueId),
+    collaborators: Vec<(Entity, UniqueId)>,
+    spectators: Vec<(Entity, UniqueId)>,
+    /// messages for everyone, optionally about a player whose name is put in front
+    announcements: Vec<(Option<Entity>, String)>,
+    /// shown in the action bar of everyone, e.g. the remaining time
+    status: Option<String>,
+    /// no one can be invited, e.g. to the boards of a race
+    closed: bool,
+}
+
+impl Participants {
+    pub fn new(owner: (Entity, UniqueId)) -> Participants {
+        Participants {
+            owner,
+            collaborators: Vec::new(),
+            spectators: Vec::new(),
+            announcements: Vec::new(),
+            status: None,
+            closed: false,
+        }
+    }
+
+    pub fn owner(&self) -> (Entity, UniqueId) {
+        self.owner
+    }
+
+    /// the owner and all collaborators
+    pub fn players(&self) -> impl Iterator<Item = (Entity, UniqueId)> + '_ {
+        std::iter::once(self.owner).chain(self.collaborators.iter().copied())
+    }
+
+    /// players and spectators
+    pub fn everyone(&self) -> impl Iterator<Item = (Entity, UniqueId)> + '_ {
+        self.players().chain(self.spectators.iter().copied())
+    }
+
+    pub fn is_participant(&self, player: Entity) -> bool {
+        self.everyone().any(|p| p.0 == player)
+    }
+
+    /// only the owner and collaborators are allowed to interact with a game
+    pub fn can_interact(&self, player: Entity) -> bool {
+        self.players().any(|p| p.0 == player)
+    }
+
+    /// stops the owner from inviting anyone else
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    /// returns false if the player already was allowed to interact or the game is closed
+    pub fn invite(&mut self, player: (Entity, UniqueId)) -> bool {
+        if self.closed || self.can_interact(player.0) {
+            return false;
+        }
+        self.spectators.retain(|s| s.0 != player.0);
+        self.collaborators.push(player);
+        return true;
+    }
+
+    /// returns false if the player already was a participant
+    pub fn add_spectator(&mut self, player: (Entity, UniqueId)) -> bool {
+        if self.is_participant(player.0) {
+            return false;
+        }
+        self.spectators.push(player);
+        return true;
+    }
+
+    /// removes a collaborator or spectator, the owner cant leave their own game
+    pub fn remove(&mut self, player: Entity) -> bool {
+        let amt = self.collaborators.len() + self.spectators.len();
+        self.collaborators.retain(|c| c.0 != player);
+        self.spectators.retain(|s| s.0 != player);
+        return amt != self.collaborators.len() + self.spectators.len();
+    }
+
+    /// queues a chat message for everyone in the game, it is sent by the CustomGamePlugin
+    pub fn announce(&mut self, msg: impl Into<String>) {
+        self.announcements.push((None, msg.into()));
+    }
+
+    /// like [Participants::announce], but starts the message with the name of the player
+    pub fn announce_about(&mut self, player: Entity, msg: impl Into<String>) {
+        self.announcements.push((Some(player), msg.into()));
+    }
+
+    pub fn take_announcements(&mut self) -> Vec<(Option<Entity>, String)> {
+        std::mem::take(&mut self.announcements)
+    }
+
+    /// replaces the action bar of everyone in the game, it is sent by the CustomGamePlugin
+    pub fn show_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    pub fn take_status(&mut self) -> Option<String> {
+        self.status.take()
+    }
+}
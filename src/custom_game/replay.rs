@@ -0,0 +1,337 @@
+use std::collections::VecDeque;
+
+use super::{debounce::ClickKind, CustomGameContainer};
+use crate::{options::GameOptions, seed::GameSeed};
+use valence::prelude::*;
+
+/// version 2 added the game options
+const FORMAT_VERSION: u8 = 2;
+/// how long a finished replay stays in the world before it is removed
+const REPLAY_LINGER_TICKS: u32 = 20 * 5;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RecordedInput {
+    /// the amount of game ticks before this input
+    pub tick: u32,
+    pub click: ClickKind,
+    /// the clicked position, relative to the origin of the game
+    pub offset: BlockPos,
+}
+
+/// The log of a game, everything needed to play it out again: which game it was,
+/// with which seed, options and facing which way it was started and the inputs it received
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct GameRecording {
+    pub game: String,
+    pub seed: GameSeed,
+    pub yaw: f32,
+    pub options: GameOptions,
+    ticks: u32,
+    inputs: Vec<RecordedInput>,
+}
+
+impl GameRecording {
+    pub fn new(game: &str, seed: GameSeed, yaw: f32, options: GameOptions) -> GameRecording {
+        GameRecording {
+            game: game.to_string(),
+            seed,
+            yaw,
+            options,
+            ticks: 0,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    pub fn record(&mut self, click: ClickKind, click_pos: &BlockPos, origin: &BlockPos) {
+        self.inputs.push(RecordedInput {
+            tick: self.ticks,
+            click,
+            offset: BlockPos::new(
+                click_pos.x - origin.x,
+                click_pos.y - origin.y,
+                click_pos.z - origin.z,
+            ),
+        });
+    }
+
+    pub fn inputs(&self) -> &[RecordedInput] {
+        &self.inputs
+    }
+
+    /// A compact binary format: a small header with the game, seed and direction,
+    /// followed by the inputs as varints, storing the ticks as the difference to the previous input.
+    /// The game name and options are prefixed by their length in one byte, longer ones cant be encoded
+    pub fn encode(&self) -> Option<Vec<u8>> {
+        let mut data = vec![FORMAT_VERSION, u8::try_from(self.game.len()).ok()?];
+        data.extend_from_slice(self.game.as_bytes());
+        data.extend_from_slice(&self.seed.value.to_le_bytes());
+        data.push(self.seed.daily as u8);
+        data.extend_from_slice(&self.yaw.to_le_bytes());
+        let options = self.options.to_string();
+        data.push(u8::try_from(options.len()).ok()?);
+        data.extend_from_slice(options.as_bytes());
+
+        write_varint(&mut data, self.inputs.len() as u64);
+        let mut last_tick = 0;
+        for input in &self.inputs {
+            write_varint(&mut data, (input.tick - last_tick) as u64);
+            last_tick = input.tick;
+            data.push(match input.click {
+                ClickKind::Left => 0,
+                ClickKind::Right => 1,
+                // 2 was meant for the off hand, which is never recorded
+                ClickKind::Hint => 3,
+                ClickKind::Step => 4,
+            });
+            for coord in [input.offset.x, input.offset.y, input.offset.z] {
+                write_varint(&mut data, zigzag(coord));
+            }
+        }
+        Some(data)
+    }
+
+    pub fn decode(data: &[u8]) -> Option<GameRecording> {
+        let mut reader = Reader(data);
+        let version = reader.byte()?;
+        if version > FORMAT_VERSION {
+            return None;
+        }
+        let name_len = reader.byte()? as usize;
+        let game = String::from_utf8(reader.take(name_len)?.to_vec()).ok()?;
+        let seed = GameSeed {
+            value: u64::from_le_bytes(reader.take(8)?.try_into().ok()?),
+            daily: reader.byte()? != 0,
+        };
+        let yaw = f32::from_le_bytes(reader.take(4)?.try_into().ok()?);
+        let options = match version {
+            1 => GameOptions::default(),
+            _ => {
+                let len = reader.byte()? as usize;
+                let options = std::str::from_utf8(reader.take(len)?).ok()?;
+                GameOptions::parse(options.split_whitespace()).ok()?
+            }
+        };
+
+        let input_amt = reader.varint()?;
+        let mut inputs = Vec::new();
+        let mut tick = 0;
+        for _ in 0..input_amt {
+            tick += reader.varint()? as u32;
+            let click = match reader.byte()? {
+                0 => ClickKind::Left,
+                1 => ClickKind::Right,
+                3 => ClickKind::Hint,
+                4 => ClickKind::Step,
+                _ => return None,
+            };
+            let offset = BlockPos::new(
+                unzigzag(reader.varint()?),
+                unzigzag(reader.varint()?),
+                unzigzag(reader.varint()?),
+            );
+            inputs.push(RecordedInput {
+                tick,
+                click,
+                offset,
+            });
+        }
+        Some(GameRecording {
+            game,
+            seed,
+            yaw,
+            options,
+            ticks: tick,
+            inputs,
+        })
+    }
+}
+
+/// Plays the inputs of a recording into a freshly generated game with the same seed.
+/// The game is ticked by the playback instead of the CustomGamePlugin,
+/// a speed of 2.0 ticks it twice per server tick
+#[derive(Component)]
+pub struct ReplayPlayback {
+    inputs: VecDeque<RecordedInput>,
+    speed: f32,
+    progress: f32,
+    tick: u32,
+    finished_ticks: u32,
+}
+
+impl ReplayPlayback {
+    pub fn new(recording: &GameRecording, speed: f32) -> ReplayPlayback {
+        ReplayPlayback {
+            inputs: recording.inputs.iter().copied().collect(),
+            speed,
+            progress: 0.0,
+            tick: 0,
+            finished_ticks: 0,
+        }
+    }
+}
+
+pub fn play_replays(
+    mut replays: Query<(Entity, &mut CustomGameContainer, &mut ReplayPlayback)>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut commands: Commands,
+) {
+    for (entity, mut game, mut playback) in replays.iter_mut() {
+        let playback = &mut *playback;
+        if game.should_despawn() {
+            // despawn_games takes care of it
+            continue;
+        }
+        let layer = layers.single_mut().into_inner();
+        let viewer = game.participants().owner().0;
+
+        playback.progress += playback.speed;
+        while playback.progress >= 1.0 {
+            playback.progress -= 1.0;
+            while playback
+                .inputs
+                .front()
+                .is_some_and(|i| i.tick == playback.tick)
+            {
+                let input = playback.inputs.pop_front().unwrap();
+                let origin = game.origin();
+                let pos = origin.offset(input.offset.x, input.offset.y, input.offset.z);
+                match input.click {
+                    ClickKind::Left => {
+                        game.click_left(&pos, viewer, layer);
+                    }
+                    ClickKind::Right => {
+                        game.click_right(&pos, viewer, layer);
+                    }
+                    ClickKind::Hint => {
+                        game.hint(viewer, layer);
+                    }
+                    ClickKind::Step => {
+                        game.step_on(&pos, viewer, layer);
+                    }
+                }
+            }
+            game.tick(layer);
+            playback.tick += 1;
+        }
+
+        if playback.inputs.is_empty() {
+            playback.finished_ticks += 1;
+            if playback.finished_ticks > REPLAY_LINGER_TICKS {
+                game.reset(layer);
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn write_varint(data: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        data.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+fn zigzag(value: i32) -> u64 {
+    ((value << 1) ^ (value >> 31)) as u32 as u64
+}
+
+fn unzigzag(value: u64) -> i32 {
+    let value = value as u32;
+    (value >> 1) as i32 ^ -((value & 1) as i32)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, amt: usize) -> Option<&'a [u8]> {
+        if self.0.len() < amt {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(amt);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording(options: GameOptions) -> GameRecording {
+        let origin = BlockPos::new(10, 64, -5);
+        let mut recording =
+            GameRecording::new("minesweeper", GameSeed::from_value(1234), -90.0, options);
+        recording.tick();
+        recording.record(ClickKind::Left, &BlockPos::new(7, 63, -25), &origin);
+        for _ in 0..300 {
+            recording.tick();
+        }
+        recording.record(ClickKind::Right, &BlockPos::new(12, 64, -4), &origin);
+        recording.record(ClickKind::Hint, &origin, &origin);
+        recording.tick();
+        recording.record(ClickKind::Step, &BlockPos::new(-200, 64, 300), &origin);
+        recording
+    }
+
+    #[test]
+    fn round_trip() {
+        let options = GameOptions::parse(["lives=3", "timed"]).unwrap();
+        let recording = recording(options);
+        let decoded = GameRecording::decode(&recording.encode().unwrap()).unwrap();
+        assert_eq!(decoded, recording);
+        assert_eq!(decoded.inputs()[0].offset, BlockPos::new(-3, -1, -20));
+    }
+
+    #[test]
+    fn version_1_has_no_options() {
+        let recording = recording(GameOptions::default());
+        let data = recording.encode().unwrap();
+        // the name, seed, daily flag and yaw come before the length of the options
+        let options_at = 2 + recording.game.len() + 8 + 1 + 4;
+        assert_eq!(data[options_at], 0);
+        let mut v1 = vec![1];
+        v1.extend_from_slice(&data[1..options_at]);
+        v1.extend_from_slice(&data[options_at + 1..]);
+        assert_eq!(GameRecording::decode(&v1), Some(recording));
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        let data = recording(GameOptions::default()).encode().unwrap();
+        for len in 0..data.len() {
+            assert_eq!(GameRecording::decode(&data[..len]), None);
+        }
+    }
+
+    #[test]
+    fn long_names_arent_encoded() {
+        let recording = GameRecording::new(
+            &"a".repeat(256),
+            GameSeed::from_value(1),
+            0.0,
+            GameOptions::default(),
+        );
+        assert_eq!(recording.encode(), None);
+    }
+}
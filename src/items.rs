@@ -3,9 +3,12 @@ use valence::{
     prelude::*,
 };
 
+use crate::seed::GameSeed;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum StartItemType {
-    RSG5,
-    RSG7,
+    /// repeat sequence, the size of the grid is one of the game options
+    RSG,
     Minesweeper,
     Minesweeper3D20x20,
     Minesweeper3D10x10,
@@ -14,14 +17,71 @@ pub enum StartItemType {
 impl StartItemType {
     pub fn all_types() -> impl IntoIterator<Item = StartItemType> {
         [
-            StartItemType::RSG5,
-            StartItemType::RSG7,
+            StartItemType::RSG,
             StartItemType::Minesweeper,
             StartItemType::Minesweeper3D20x20,
             StartItemType::Minesweeper3D10x10,
         ]
     }
 
+    /// the name used to select a game in chat commands
+    pub fn name(&self) -> &'static str {
+        match self {
+            StartItemType::RSG => "rsg",
+            StartItemType::Minesweeper => "minesweeper",
+            StartItemType::Minesweeper3D20x20 => "minesweeper3d20",
+            StartItemType::Minesweeper3D10x10 => "minesweeper3d10",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<StartItemType> {
+        StartItemType::all_types()
+            .into_iter()
+            .find(|t| t.name().eq_ignore_ascii_case(name))
+    }
+
+    /// like [StartItemType::from_name], but also takes rsg5 and rsg7,
+    /// the names of repeat sequence games from before they had a size option, along with their size
+    pub fn from_alias(name: &str) -> Option<(StartItemType, Option<(usize, usize)>)> {
+        match name.to_ascii_lowercase().as_str() {
+            "rsg5" => Some((StartItemType::RSG, Some((5, 5)))),
+            "rsg7" => Some((StartItemType::RSG, Some((7, 7)))),
+            _ => Some((StartItemType::from_name(name)?, None)),
+        }
+    }
+
+    /// the 20x20x20 board has no game yet, it is rejected when started
+    pub fn is_playable(&self) -> bool {
+        *self != StartItemType::Minesweeper3D20x20
+    }
+
+    pub fn is_minesweeper(&self) -> bool {
+        *self != StartItemType::RSG
+    }
+
+    fn code_prefix(&self) -> char {
+        match self {
+            StartItemType::RSG => 'R',
+            StartItemType::Minesweeper => 'M',
+            StartItemType::Minesweeper3D20x20 => 'D',
+            StartItemType::Minesweeper3D10x10 => 'C',
+        }
+    }
+
+    /// a short code to share in chat, it contains the game and its seed
+    pub fn seed_code(&self, seed: &GameSeed) -> String {
+        format!("{}{}", self.code_prefix(), seed.code())
+    }
+
+    pub fn from_seed_code(code: &str) -> Option<(StartItemType, GameSeed)> {
+        let mut chars = code.chars();
+        let prefix = chars.next()?.to_ascii_uppercase();
+        let item_type = StartItemType::all_types()
+            .into_iter()
+            .find(|t| t.code_prefix() == prefix)?;
+        Some((item_type, GameSeed::from_code(chars.as_str())?))
+    }
+
     pub fn get_start_item_type(item: &ItemStack) -> Option<StartItemType> {
         if item.item != ItemKind::Stick {
             return None;
@@ -29,8 +89,7 @@ impl StartItemType {
         if let Value::Compound(c) = item.nbt.clone()?.get("display")? {
             if let Value::String(s) = c.get("Name")? {
                 match s.as_str() {
-                    "\"Repeat Sequence 5x5\"" => Some(StartItemType::RSG5),
-                    "\"Repeat Sequence 7x7\"" => Some(StartItemType::RSG7),
+                    "\"Repeat Sequence\"" => Some(StartItemType::RSG),
                     "\"Minesweeper\"" => Some(StartItemType::Minesweeper),
                     "\"Minesweeper3d20x20\"" => Some(StartItemType::Minesweeper3D20x20),
                     "\"Minesweeper3d10x10\"" => Some(StartItemType::Minesweeper3D10x10),
@@ -47,21 +106,12 @@ impl StartItemType {
 
     pub fn create_start_item(item_type: StartItemType) -> ItemStack {
         match item_type {
-            StartItemType::RSG5 => ItemStack::new(
-                ItemKind::Stick,
-                1,
-                Some(compound! {
-                    "display" => compound! {
-                        "Name" => "\"Repeat Sequence 5x5\"",
-                    }
-                }),
-            ),
-            StartItemType::RSG7 => ItemStack::new(
+            StartItemType::RSG => ItemStack::new(
                 ItemKind::Stick,
                 1,
                 Some(compound! {
                     "display" => compound! {
-                        "Name" => "\"Repeat Sequence 7x7\"",
+                        "Name" => "\"Repeat Sequence\"",
                     }
                 }),
             ),
@@ -3,15 +3,23 @@
 mod custom_game;
 mod items;
 mod minesweeper;
+mod options;
 mod postgres_wrapper;
 mod repeat_sequence;
+mod seed;
 
 use items::*;
 use minesweeper::MineSweeperGame;
 use minesweeper::MineSweeperGame3d;
+use minesweeper::{import_game, BoardText, DemoEvent, DemoPlugin, RacePlugin, StartRaceEvent};
+use options::GameOptions;
 use postgres::NoTls;
 use postgres_wrapper::PostgresWrapper;
-use repeat_sequence::RepeatSequenceGame;
+use repeat_sequence::{
+    RepeatSequenceGame, SequenceDisplay, SequenceLayout, SequenceMode, SequenceSpeed,
+    SequenceVariant,
+};
+use seed::GameSeed;
 
 use valence::message::ChatMessageEvent;
 use valence::world_border::WorldBorderBundle;
@@ -22,7 +30,9 @@ use valence::{
     prelude::*,
 };
 
-use custom_game::{CustomGameContainer, CustomGamePlugin};
+use custom_game::{
+    CustomGame, CustomGameContainer, CustomGamePlugin, GameRecording, HintEvent, ReplayPlayback,
+};
 
 fn main() {
     App::new()
@@ -35,6 +45,9 @@ fn main() {
             level: Level::INFO,
             ..Default::default()
         }))
+        .add_event::<StartGameEvent>()
+        .add_event::<StartReplayEvent>()
+        .add_event::<ImportBoardEvent>()
         .add_systems(Startup, setup)
         .add_systems(
             Update,
@@ -43,9 +56,12 @@ fn main() {
                 despawn_disconnected_clients,
                 item_use_listener,
                 chat_handler,
+                start_games,
+                start_replays,
+                start_imported_games,
             ),
         )
-        .add_plugins(CustomGamePlugin)
+        .add_plugins((CustomGamePlugin, RacePlugin, DemoPlugin))
         .run();
 }
 
@@ -74,6 +90,88 @@ fn setup(
         comp_time INT,
         bomb_amt INT,
         player_uuid BYTEA
+);",
+            &[],
+        )
+        .unwrap();
+        c.execute(
+            "ALTER TABLE minesweeper_games
+        ADD COLUMN IF NOT EXISTS three_bv INT,
+        ADD COLUMN IF NOT EXISTS left_clicks INT,
+        ADD COLUMN IF NOT EXISTS right_clicks INT,
+        ADD COLUMN IF NOT EXISTS chord_clicks INT,
+        ADD COLUMN IF NOT EXISTS efficiency REAL,
+        ADD COLUMN IF NOT EXISTS three_bv_per_sec REAL,
+        ADD COLUMN IF NOT EXISTS comp_time_ms INT,
+        ADD COLUMN IF NOT EXISTS seed BIGINT,
+        ADD COLUMN IF NOT EXISTS daily BOOLEAN DEFAULT FALSE,
+        ADD COLUMN IF NOT EXISTS team_size INT DEFAULT 1,
+        ADD COLUMN IF NOT EXISTS hints INT DEFAULT 0,
+        ADD COLUMN IF NOT EXISTS lives INT DEFAULT 1,
+        ADD COLUMN IF NOT EXISTS mistakes INT DEFAULT 0,
+        ADD COLUMN IF NOT EXISTS time_limit_ms INT,
+        ADD COLUMN IF NOT EXISTS flag_win BOOLEAN DEFAULT FALSE,
+        ADD COLUMN IF NOT EXISTS cells_opened INT,
+        ADD COLUMN IF NOT EXISTS correct_flags INT
+;",
+            &[],
+        )
+        .unwrap();
+        // games from before millisecond times only have their time in ticks
+        c.execute(
+            "UPDATE minesweeper_games SET comp_time_ms = comp_time * 50 WHERE comp_time_ms IS NULL",
+            &[],
+        )
+        .unwrap();
+        c.execute(
+            "CREATE TABLE IF NOT EXISTS replays (
+        id SERIAL PRIMARY KEY,
+        date TIMESTAMP,
+        game TEXT,
+        seed BIGINT,
+        player_uuid BYTEA,
+        data BYTEA
+);",
+            &[],
+        )
+        .unwrap();
+        c.execute(
+            "ALTER TABLE rsg_games
+        ADD COLUMN IF NOT EXISTS seed BIGINT,
+        ADD COLUMN IF NOT EXISTS daily BOOLEAN DEFAULT FALSE,
+        ADD COLUMN IF NOT EXISTS speed TEXT DEFAULT 'classic',
+        ADD COLUMN IF NOT EXISTS mode TEXT DEFAULT 'standard',
+        ADD COLUMN IF NOT EXISTS display TEXT DEFAULT 'plain',
+        ADD COLUMN IF NOT EXISTS variant TEXT DEFAULT 'normal',
+        ADD COLUMN IF NOT EXISTS layout TEXT DEFAULT 'wall',
+        ADD COLUMN IF NOT EXISTS missed_clicks INT,
+        ADD COLUMN IF NOT EXISTS total_time_ms INT,
+        ADD COLUMN IF NOT EXISTS score INT,
+        ADD COLUMN IF NOT EXISTS correct_inputs INT,
+        ADD COLUMN IF NOT EXISTS average_reaction_ms INT,
+        ADD COLUMN IF NOT EXISTS best_reaction_ms INT,
+        ADD COLUMN IF NOT EXISTS reaction_times_ms INT[],
+        ADD COLUMN IF NOT EXISTS width INT,
+        ADD COLUMN IF NOT EXISTS height INT,
+        ADD COLUMN IF NOT EXISTS team_size INT DEFAULT 1
+;",
+            &[],
+        )
+        .unwrap();
+        // games from before rectangular grids only have a size
+        c.execute(
+            "UPDATE rsg_games SET width = size, height = size WHERE width IS NULL",
+            &[],
+        )
+        .unwrap();
+        c.execute(
+            "CREATE TABLE IF NOT EXISTS minesweeper_races (
+        date TIMESTAMP,
+        seed BIGINT,
+        size INT,
+        player_count INT,
+        winner_uuid BYTEA,
+        comp_time_ms INT
 );",
             &[],
         )
@@ -145,7 +243,10 @@ fn init_clients(
         pos.set([0.0, 65.0, 0.0]);
         *game_mode = GameMode::Creative;
 
-        for e in StartItemType::all_types() {
+        for e in StartItemType::all_types()
+            .into_iter()
+            .filter(|t| t.is_playable())
+        {
             let slot_num = inv.first_empty_slot_in(36..45).unwrap();
             inv.set_slot(slot_num, StartItemType::create_start_item(e));
         }
@@ -154,15 +255,15 @@ fn init_clients(
             client.send_chat_message(format!("Your Highest Streak: {}", streak));
         }
 
-        if let Some((size, dim, comp_time)) = database.get_minesweeper_fastest(uuid) {
+        if let Some((size, dim, comp_time_ms)) = database.get_minesweeper_fastest(uuid) {
             let d = match dim {
                 2 => "2D",
                 3 => "3D",
                 _ => unreachable!(),
             };
             client.send_chat_message(format!(
-                "Your fastest minesweeper game took: {} seconds, it was a {dim}x{dim} {d} game",
-                comp_time / 20
+                "Your fastest minesweeper game took: {:.3} seconds, it was a {size}x{size} {d} game",
+                comp_time_ms as f32 / 1000.0
             ));
         }
 
@@ -175,53 +276,650 @@ fn init_clients(
     }
 }
 
+/// starts a game for a player, sent when a start item is used or a game is started from chat
+#[derive(Event)]
+struct StartGameEvent {
+    player: Entity,
+    game: StartItemType,
+    seed: GameSeed,
+    options: GameOptions,
+}
+
 fn item_use_listener(
     mut item_interacts: EventReader<InteractItemEvent>,
-    players: Query<(&Look, &Inventory, &HeldItem, &Position, &UniqueId)>,
-    mut commands: Commands,
+    players: Query<(&Inventory, &HeldItem)>,
+    mut start_games: EventWriter<StartGameEvent>,
 ) {
     for interaction in item_interacts.iter() {
-        let (look, inv, held_item, pos, uuid) = players.get(interaction.client).unwrap();
+        let (inv, held_item) = players.get(interaction.client).unwrap();
         let held_item = inv.slot(held_item.slot());
         if let Some(item_type) = StartItemType::get_start_item_type(held_item) {
-            match item_type {
-                StartItemType::RSG5 => {
-                    let rsg5 =
-                        RepeatSequenceGame::<5>::new(pos, look.yaw, (interaction.client, *uuid));
-                    commands.spawn(CustomGameContainer(Box::new(rsg5)));
-                }
-                StartItemType::RSG7 => {
-                    let rsg7 =
-                        RepeatSequenceGame::<7>::new(pos, look.yaw, (interaction.client, *uuid));
-                    commands.spawn(CustomGameContainer(Box::new(rsg7)));
-                }
-                StartItemType::Minesweeper => {
-                    let msg = MineSweeperGame::<20>::new(pos, (interaction.client, *uuid));
-                    commands.spawn(CustomGameContainer(Box::new(msg)));
-                }
-                // StartItemType::Minesweeper3D20x20 => {
-                //     let msg = MineSweeperGame3d::<20>::new(pos, (interaction.client, *uuid));
-                //     commands.spawn(CustomGameContainer(Box::new(msg)));
-                // }
-                StartItemType::Minesweeper3D10x10 => {
-                    let msg = MineSweeperGame3d::<10>::new(pos, (interaction.client, *uuid));
-                    commands.spawn(CustomGameContainer(Box::new(msg)));
-                }
-                _ => (),
+            start_games.send(StartGameEvent {
+                player: interaction.client,
+                game: item_type,
+                seed: GameSeed::random(),
+                options: GameOptions::default(),
+            });
+        }
+    }
+}
+
+/// watches a recorded game, sent by the !replay chat command
+#[derive(Event)]
+struct StartReplayEvent {
+    viewer: Entity,
+    recording: GameRecording,
+    speed: f32,
+}
+
+fn create_game(
+    game: StartItemType,
+    pos: &Position,
+    yaw: f32,
+    player: (Entity, UniqueId),
+    seed: GameSeed,
+    options: &GameOptions,
+) -> Option<Box<dyn CustomGame + Send + Sync>> {
+    match game {
+        StartItemType::RSG => Some(Box::new(
+            RepeatSequenceGame::new(
+                pos,
+                yaw,
+                player,
+                seed,
+                options
+                    .sequence_size
+                    .unwrap_or(repeat_sequence::DEFAULT_SIZE),
+            )
+            .with_options(options),
+        )),
+        StartItemType::Minesweeper => Some(Box::new(
+            MineSweeperGame::<20>::new(pos, player, seed).with_options(options),
+        )),
+        StartItemType::Minesweeper3D10x10 => Some(Box::new(
+            MineSweeperGame3d::<10>::new(pos, player, seed).with_options(options),
+        )),
+        // rejected by GameOptions::check_game before it gets here
+        StartItemType::Minesweeper3D20x20 => None,
+    }
+}
+
+fn start_games(
+    mut start_games: EventReader<StartGameEvent>,
+    players: Query<(&Look, &Position, &UniqueId)>,
+    mut commands: Commands,
+) {
+    for event in start_games.iter() {
+        let Ok((look, pos, uuid)) = players.get(event.player) else {
+            continue;
+        };
+        let player = (event.player, *uuid);
+        let Some(mut game) = create_game(
+            event.game,
+            pos,
+            look.yaw,
+            player,
+            event.seed,
+            &event.options,
+        ) else {
+            continue;
+        };
+        if event.seed.daily {
+            game.participants_mut()
+                .announce("Started todays daily challenge, good luck!");
+        } else {
+            // the options arent part of the code, they are given to !play along with it
+            let code = event.game.seed_code(&event.seed);
+            let options = event.options.to_string();
+            let command = match options.is_empty() {
+                true => format!("!play {code}"),
+                false => format!("!play {code} {options}"),
+            };
+            game.participants_mut().announce(format!(
+                "Seed code: {code}, play this game again with {command}"
+            ));
+        }
+        if !event.options.is_standard() {
+            game.participants_mut().announce(format!(
+                "Playing with {}, this game doesnt count for the standard leaderboards",
+                event.options
+            ));
+        }
+        // the recording takes the seed from the game, so a replay generates the same board
+        let recording = GameRecording::new(event.game.name(), game.seed(), look.yaw, event.options);
+        commands.spawn((CustomGameContainer(game), recording));
+    }
+}
+
+fn start_replays(
+    mut start_replays: EventReader<StartReplayEvent>,
+    players: Query<(&Position, &UniqueId)>,
+    mut commands: Commands,
+) {
+    for event in start_replays.iter() {
+        let Ok((pos, uuid)) = players.get(event.viewer) else {
+            continue;
+        };
+        let Some((game_type, size)) = StartItemType::from_alias(&event.recording.game) else {
+            tracing::warn!(
+                "A replay of an unknown game was loaded: {}",
+                event.recording.game
+            );
+            continue;
+        };
+        // recordings of rsg5 and rsg7 games have no size option
+        let options = GameOptions {
+            sequence_size: event.recording.options.sequence_size.or(size),
+            ..event.recording.options
+        };
+        // the replay is built where the viewer stands, facing the same way as the recorded game
+        let Some(mut game) = create_game(
+            game_type,
+            pos,
+            event.recording.yaw,
+            (event.viewer, *uuid),
+            event.recording.seed,
+            &options,
+        ) else {
+            continue;
+        };
+        game.participants_mut().announce(format!(
+            "Replaying a {} game at {}x speed",
+            game_type.name(),
+            event.speed
+        ));
+        commands.spawn((
+            CustomGameContainer(game),
+            ReplayPlayback::new(&event.recording, event.speed),
+        ));
+    }
+}
+
+/// where boards are saved with !export and loaded with !import
+const BOARD_DIR: &str = "boards";
+
+/// starts a game on a board loaded from a file, sent by the !import chat command
+#[derive(Event)]
+struct ImportBoardEvent {
+    player: Entity,
+    name: String,
+    board: String,
+}
+
+fn board_path(name: &str) -> Option<std::path::PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+    return Some(std::path::Path::new(BOARD_DIR).join(format!("{name}.txt")));
+}
+
+fn start_imported_games(
+    mut imports: EventReader<ImportBoardEvent>,
+    mut players: Query<(&Position, &UniqueId, &mut Client)>,
+    mut commands: Commands,
+) {
+    for event in imports.iter() {
+        let Ok((pos, uuid, mut client)) = players.get_mut(event.player) else {
+            continue;
+        };
+        match import_game(&event.board, pos, (event.player, *uuid)) {
+            Ok(mut game) => {
+                game.participants_mut().announce(format!(
+                    "Loaded the board {}, it doesnt count for the leaderboards",
+                    event.name
+                ));
+                commands.spawn(CustomGameContainer(game));
             }
+            Err(err) => client.send_chat_message(format!("Couldnt load {}: {err}", event.name)),
         }
     }
 }
 
 fn chat_handler(
     mut messages: EventReader<ChatMessageEvent>,
-    mut players: Query<(&mut Client, &Username)>,
+    mut players: Query<(Entity, &mut Client, &Username, &UniqueId)>,
+    mut games: Query<&mut CustomGameContainer>,
+    mut command_events: CommandEvents,
+    database: Local<PostgresWrapper>,
 ) {
     for message in messages.iter() {
-        let sender = players.get(message.client).unwrap().1 .0.clone();
         let msg: &str = message.message.as_ref();
+        if let Some(command) = msg.strip_prefix('!') {
+            let reply = run_chat_command(
+                command,
+                message.client,
+                &players,
+                &mut games,
+                &mut command_events,
+                &database,
+            );
+            if !reply.is_empty() {
+                if let Ok(mut sender) = players.get_mut(message.client) {
+                    sender.1.send_chat_message(reply);
+                }
+            }
+            continue;
+        }
+        let sender = players.get(message.client).unwrap().2 .0.clone();
         for mut player in players.iter_mut() {
-            player.0.send_chat_message(format!("<{}> {}", sender, msg))
+            player.1.send_chat_message(format!("<{}> {}", sender, msg))
+        }
+    }
+}
+
+/// the game of the !daily and !dailyboard commands and the size of the grid, e.g. `rsg 7x7` or `rsg7`
+fn daily_game(
+    name: &str,
+    size: Option<&str>,
+) -> Result<(StartItemType, Option<(usize, usize)>), String> {
+    let Some((game, alias_size)) = StartItemType::from_alias(name) else {
+        return Err(format!("Unknown game {name}"));
+    };
+    let Some(size) = size else {
+        return Ok((game, alias_size));
+    };
+    match repeat_sequence::parse_size(size) {
+        Some(size) if game == StartItemType::RSG => Ok((game, Some(size))),
+        _ => Err(format!(
+            "Invalid size {size}, only rsg grids have a size: <2-9>x<2-9>"
+        )),
+    }
+}
+
+/// the events chat commands are handled with
+type CommandEvents<'w> = (
+    EventWriter<'w, StartGameEvent>,
+    EventWriter<'w, StartReplayEvent>,
+    EventWriter<'w, StartRaceEvent>,
+    EventWriter<'w, HintEvent>,
+    EventWriter<'w, DemoEvent>,
+    EventWriter<'w, ImportBoardEvent>,
+);
+
+/// handles chat messages starting with '!', returns the answer for the sender
+fn run_chat_command(
+    command: &str,
+    sender: Entity,
+    players: &Query<(Entity, &mut Client, &Username, &UniqueId)>,
+    games: &mut Query<&mut CustomGameContainer>,
+    (start_games, start_replays, start_races, hints, demos, imports): &mut CommandEvents,
+    database: &PostgresWrapper,
+) -> String {
+    let mut args = command.split_whitespace();
+    let (cmd, name) = (args.next().unwrap_or_default(), args.next());
+    let target = name.and_then(|name| players.iter().find(|p| p.2 .0 == name));
+
+    match (cmd, name, target) {
+        ("invite", Some(_), Some((target, _, username, uuid))) => {
+            let mut invited = 0;
+            for mut game in games.iter_mut() {
+                if game.participants().owner().0 == sender
+                    && game.participants_mut().invite((target, *uuid))
+                {
+                    invited += 1;
+                }
+            }
+            match invited {
+                0 => "You have no game to invite to".to_string(),
+                _ => format!("{username} can now play in your game"),
+            }
+        }
+        ("spectate", Some(_), Some((target, _, username, _))) => {
+            let sender_uuid = *players.get(sender).unwrap().3;
+            let mut spectating = 0;
+            for mut game in games.iter_mut() {
+                if game.participants().owner().0 == target
+                    && game.participants_mut().add_spectator((sender, sender_uuid))
+                {
+                    spectating += 1;
+                }
+            }
+            match spectating {
+                0 => format!("{username} has no game to spectate"),
+                _ => format!("You are now spectating {username}"),
+            }
+        }
+        ("leave", None, None) => {
+            let mut left = 0;
+            for mut game in games.iter_mut() {
+                if game.participants_mut().remove(sender) {
+                    left += 1;
+                }
+            }
+            format!("You left {left} game(s)")
+        }
+        ("leaderboard", Some(game @ ("rsg" | "rsg5" | "rsg7")), _) => {
+            let mut size = StartItemType::from_alias(game)
+                .and_then(|(_, size)| size)
+                .unwrap_or(repeat_sequence::DEFAULT_SIZE);
+            let mut speed = SequenceSpeed::Classic;
+            let mut mode = SequenceMode::Standard;
+            let mut display = SequenceDisplay::Plain;
+            let mut variant = SequenceVariant::Normal;
+            let mut layout = SequenceLayout::Wall;
+            for arg in args {
+                if let Some(s) = SequenceSpeed::from_name(arg) {
+                    speed = s;
+                } else if let Some(m) = SequenceMode::from_name(arg) {
+                    mode = m;
+                } else if let Some(d) = SequenceDisplay::from_name(arg) {
+                    display = d;
+                } else if let Some(v) = SequenceVariant::from_name(arg) {
+                    variant = v;
+                } else if let Some(l) = SequenceLayout::from_name(arg) {
+                    layout = l;
+                } else if let Some(s) = repeat_sequence::parse_size(arg) {
+                    size = s;
+                } else {
+                    return "Unknown category, speeds: classic, progressive, fast, \
+                    modes: standard, suddendeath, relaxed, timeattack, displays: plain, music, audio, \
+                    variants: normal, reverse, mirror, newest<1-9>, layouts: wall, floor, cube, \
+                    sizes: <2-9>x<2-9>"
+                        .to_string();
+                }
+            }
+            let variant = variant.name();
+            let time_attack = mode == SequenceMode::TimeAttack;
+            let mut lines = vec![format!(
+                "{} {}x{} repeat sequence {} at {} speed, {} mode, {} display, \
+                {variant} variant, {} layout:",
+                if time_attack { "Most" } else { "Highest" },
+                size.0,
+                size.1,
+                if time_attack { "steps" } else { "streaks" },
+                speed.name(),
+                mode.name(),
+                display.name(),
+                layout.name()
+            )];
+            let size = (size.0 as i32, size.1 as i32);
+            let leaderboard = match time_attack {
+                true => database.get_rsg_time_attack_leaderboard(
+                    size,
+                    [
+                        speed.name(),
+                        display.name(),
+                        variant.as_str(),
+                        layout.name(),
+                    ],
+                    10,
+                ),
+                false => database.get_rsg_leaderboard(
+                    size,
+                    [
+                        speed.name(),
+                        mode.name(),
+                        display.name(),
+                        variant.as_str(),
+                        layout.name(),
+                    ],
+                    10,
+                ),
+            };
+            for (rank, (player_uuid, streak)) in leaderboard.iter().enumerate() {
+                let name = players
+                    .iter()
+                    .find(|p| p.3.as_bytes().as_slice() == player_uuid.as_slice())
+                    .map_or("offline player".to_string(), |p| p.2 .0.clone());
+                lines.push(format!("{}. {name}: {streak}", rank + 1));
+            }
+            lines.join("\n")
+        }
+        ("leaderboard", first, _) => {
+            let categories: Vec<&str> = first.into_iter().chain(args).collect();
+            let dim = match categories.contains(&"3d") {
+                true => 3,
+                false => 2,
+            };
+            let timed = categories.contains(&"timed");
+            let mut lines = vec![format!(
+                "Best {}{dim}D minesweeper games by 3BV/s:",
+                if timed { "timed " } else { "" }
+            )];
+            for (rank, entry) in database
+                .get_minesweeper_leaderboard(dim, timed, 10)
+                .iter()
+                .enumerate()
+            {
+                let name = players
+                    .iter()
+                    .find(|p| p.3.as_bytes().as_slice() == entry.player_uuid.as_slice())
+                    .map_or("offline player".to_string(), |p| p.2 .0.clone());
+                lines.push(format!(
+                    "{}. {name}: {:.2} 3BV/s, 3BV {}, {:.3}s, {:.0}% efficiency, size {}",
+                    rank + 1,
+                    entry.three_bv_per_sec,
+                    entry.three_bv,
+                    entry.comp_time_ms as f32 / 1000.0,
+                    entry.efficiency,
+                    entry.size,
+                ));
+            }
+            lines.join("\n")
+        }
+        ("start", Some(game), _) => {
+            let Some((game, size)) = StartItemType::from_alias(game) else {
+                return format!("Unknown game {game}");
+            };
+            match GameOptions::parse(args).map(|options| GameOptions {
+                sequence_size: options.sequence_size.or(size),
+                ..options
+            }) {
+                Ok(options) => match options.check_game(game) {
+                    Ok(()) => {
+                        start_games.send(StartGameEvent {
+                            player: sender,
+                            game,
+                            seed: GameSeed::random(),
+                            options,
+                        });
+                        format!("Starting {} {options}", game.name())
+                    }
+                    Err(err) => err,
+                },
+                Err(arg) => format!(
+                    "Invalid option {arg}, options: lives=<1-10>, timed, flagwin, \
+                    speed=<classic|progressive|fast>, mode=<standard|suddendeath|relaxed|timeattack>, \
+                    display=<plain|music|audio>, variant=<normal|reverse|mirror|newest<1-9>>, \
+                    layout=<wall|floor|cube>, size=<2-9>x<2-9>"
+                ),
+            }
+        }
+        ("play", Some(code), _) => {
+            let Some((game, seed)) = StartItemType::from_seed_code(code) else {
+                return format!("{code} isnt a valid seed code");
+            };
+            match GameOptions::parse(args) {
+                Ok(options) => match options.check_game(game) {
+                    Ok(()) => {
+                        start_games.send(StartGameEvent {
+                            player: sender,
+                            game,
+                            seed,
+                            options,
+                        });
+                        format!("Starting {} from seed code {code} {options}", game.name())
+                    }
+                    Err(err) => err,
+                },
+                Err(arg) => format!("Invalid option {arg}"),
+            }
+        }
+        ("daily", Some(game), _) => {
+            let (game, size) = match daily_game(game, args.next()) {
+                Ok(daily) => daily,
+                Err(err) => return err,
+            };
+            let options = GameOptions {
+                sequence_size: size,
+                ..Default::default()
+            };
+            if let Err(err) = options.check_game(game) {
+                return err;
+            }
+            start_games.send(StartGameEvent {
+                player: sender,
+                game,
+                seed: GameSeed::daily(),
+                options,
+            });
+            format!("Starting todays {} challenge {options}", game.name())
+        }
+        ("dailyboard", Some(game), _) => {
+            let seed = GameSeed::daily();
+            let (game, size) = match daily_game(game, args.next()) {
+                Ok(daily) => daily,
+                Err(err) => return err,
+            };
+            let (scores, unit, title) = match game {
+                StartItemType::RSG => {
+                    let (width, height) = size.unwrap_or(repeat_sequence::DEFAULT_SIZE);
+                    (
+                        database.get_daily_rsg_leaderboard((width as i32, height as i32), &seed),
+                        "",
+                        format!("{width}x{height} rsg"),
+                    )
+                }
+                StartItemType::Minesweeper => (
+                    database.get_daily_minesweeper_leaderboard(20, 2, &seed),
+                    "ms",
+                    game.name().to_string(),
+                ),
+                StartItemType::Minesweeper3D20x20 => (
+                    database.get_daily_minesweeper_leaderboard(20, 3, &seed),
+                    "ms",
+                    game.name().to_string(),
+                ),
+                StartItemType::Minesweeper3D10x10 => (
+                    database.get_daily_minesweeper_leaderboard(10, 3, &seed),
+                    "ms",
+                    game.name().to_string(),
+                ),
+            };
+            let mut lines = vec![format!("Todays {title} challenge:")];
+            for (rank, (player_uuid, score)) in scores.iter().enumerate() {
+                let name = players
+                    .iter()
+                    .find(|p| p.3.as_bytes().as_slice() == player_uuid.as_slice())
+                    .map_or("offline player".to_string(), |p| p.2 .0.clone());
+                lines.push(format!("{}. {name}: {score}{unit}", rank + 1));
+            }
+            lines.join("\n")
+        }
+        ("replay", Some(id), _) => {
+            let speed = args
+                .next()
+                .and_then(|s| s.parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.25, 8.0);
+            let recording = id
+                .parse()
+                .ok()
+                .and_then(|id| database.get_replay(id))
+                .and_then(|data| GameRecording::decode(&data));
+            match recording {
+                Some(recording) => {
+                    start_replays.send(StartReplayEvent {
+                        viewer: sender,
+                        recording,
+                        speed,
+                    });
+                    format!("Starting replay {id}")
+                }
+                None => format!("Couldnt load replay {id}"),
+            }
+        }
+        ("export", Some(name), _) => {
+            let Some(path) = board_path(name) else {
+                return format!("{name} isnt a valid board name");
+            };
+            let Some(board) = games
+                .iter()
+                .filter(|g| g.participants().owner().0 == sender)
+                .find_map(|g| g.export_text())
+            else {
+                return "You have no game to export".to_string();
+            };
+            let owner = players.get(sender).unwrap().3.to_string();
+            // only the player who exported a board can replace it
+            if let Ok(existing) = std::fs::read_to_string(&path) {
+                if BoardText::owner(&existing) != Some(owner.as_str()) {
+                    return format!("The board {name} belongs to someone else");
+                }
+            }
+            let board = format!("# owner {owner}\n{board}");
+            match std::fs::create_dir_all(BOARD_DIR).and_then(|_| std::fs::write(path, board)) {
+                Ok(()) => format!("Saved your board as {name}, load it with !import {name}"),
+                Err(err) => {
+                    tracing::error!("Couldnt save a board: {}", err);
+                    format!("Couldnt save the board {name}")
+                }
+            }
+        }
+        ("import", Some(name), _) => {
+            match board_path(name).and_then(|path| std::fs::read_to_string(path).ok()) {
+                Some(board) => {
+                    imports.send(ImportBoardEvent {
+                        player: sender,
+                        name: name.to_string(),
+                        board,
+                    });
+                    String::new()
+                }
+                None => format!("There is no board called {name}"),
+            }
+        }
+        ("hint", None, _) => {
+            // the games answer themselves, if they can give a hint
+            hints.send(HintEvent { player: sender });
+            String::new()
+        }
+        ("demo", Some("stop"), _) => {
+            demos.send(DemoEvent {
+                player: sender,
+                speed: None,
+            });
+            "Stopping your demo".to_string()
+        }
+        ("demo", speed, _) => {
+            let speed = speed
+                .and_then(|s| s.parse::<f32>().ok())
+                .unwrap_or(0.5)
+                .clamp(0.05, 5.0);
+            demos.send(DemoEvent {
+                player: sender,
+                speed: Some(speed),
+            });
+            format!("Running a minesweeper demo with {speed} moves per tick")
+        }
+        ("race", Some(_), _) => {
+            let mut racers = vec![sender];
+            for name in command.split_whitespace().skip(1) {
+                match players.iter().find(|p| p.2 .0 == name) {
+                    Some(p) if !racers.contains(&p.0) => racers.push(p.0),
+                    Some(_) => {}
+                    None => return format!("Couldnt find player {name}"),
+                }
+            }
+            if racers.len() < 2 {
+                return "You need someone to race against".to_string();
+            }
+            let amt = racers.len();
+            start_races.send(StartRaceEvent { players: racers });
+            format!("Starting a race between {amt} players")
+        }
+        ("invite" | "spectate", Some(name), None) => format!("Couldnt find player {name}"),
+        _ => {
+            "Commands: !invite <player>, !spectate <player>, !leave, !leaderboard [2d|3d] [timed], \
+            !leaderboard rsg [category]..., !start <game> [option=value]..., \
+            !play <code> [option=value]..., !daily <game> [WxH], !dailyboard <game> [WxH], \
+            !replay <id> [speed], !race <player>..., !hint, !demo [speed|stop], \
+            !export <name>, !import <name>"
+                .to_string()
         }
     }
 }
@@ -0,0 +1,192 @@
+use super::{
+    common::{Cell, CellContent, CellState},
+    MineSweeperGame, MineSweeperGame3d,
+};
+use crate::custom_game::CustomGame;
+use valence::prelude::*;
+
+/// A board as plain text, one character per cell and one line per row:
+/// `.` closed cell, `*` mine, `F` flagged mine, `f` flag on a safe cell, `X` mine hit in a game with lives.
+/// Opened cells are written as their number, `0` for empty cells and `+` for numbers above 9.
+/// The layers of 3D boards are separated by an empty line, lines starting with `#` are comments.
+/// Exported boards start with the comment `# owner <uuid>`, only the owner can overwrite them.
+/// Boards with only `.` and `*` are the format most other minesweeper tools use
+pub struct BoardText {
+    pub width: usize,
+    pub height: usize,
+    pub layers: usize,
+    /// if the cell is a mine and its state, layer by layer and row by row
+    pub cells: Vec<(bool, CellState)>,
+}
+
+impl BoardText {
+    pub fn parse(text: &str) -> Result<BoardText, String> {
+        let mut layers: Vec<Vec<&str>> = vec![Vec::new()];
+        for line in text.lines().map(str::trim_end) {
+            if line.starts_with('#') {
+                continue;
+            }
+            if line.is_empty() {
+                if !layers.last().unwrap().is_empty() {
+                    layers.push(Vec::new());
+                }
+            } else {
+                layers.last_mut().unwrap().push(line);
+            }
+        }
+        layers.retain(|l| !l.is_empty());
+
+        let Some(first_row) = layers.first().and_then(|l| l.first()) else {
+            return Err("the board is empty".to_string());
+        };
+        let (width, height) = (first_row.chars().count(), layers[0].len());
+        let mut cells = Vec::new();
+        for (z, layer) in layers.iter().enumerate() {
+            if layer.len() != height {
+                return Err(format!(
+                    "layer {} has {} rows instead of {height}",
+                    z + 1,
+                    layer.len()
+                ));
+            }
+            for (y, row) in layer.iter().enumerate() {
+                if row.chars().count() != width {
+                    return Err(format!(
+                        "row {} of layer {} isnt {width} cells wide",
+                        y + 1,
+                        z + 1
+                    ));
+                }
+                for c in row.chars() {
+                    cells.push(match c {
+                        '.' => (false, CellState::Closed),
+                        '*' => (true, CellState::Closed),
+                        'F' => (true, CellState::Flagged),
+                        'f' => (false, CellState::Flagged),
+                        'X' => (true, CellState::Exploded),
+                        '0'..='9' | '+' => (false, CellState::Opened),
+                        c => return Err(format!("unknown cell '{c}'")),
+                    });
+                }
+            }
+        }
+        Ok(BoardText {
+            width,
+            height,
+            layers: layers.len(),
+            cells,
+        })
+    }
+
+    /// the uuid in the `# owner` comment of an exported board
+    pub fn owner(text: &str) -> Option<&str> {
+        text.lines()
+            .find_map(|l| l.strip_prefix("# owner "))
+            .map(str::trim)
+    }
+
+    /// writes the cells of a board, which are flattened layer by layer and row by row
+    pub fn write(cells: &[Cell], width: usize, height: usize) -> String {
+        let mut text = String::new();
+        for (i, cell) in cells.iter().enumerate() {
+            if i > 0 && i % (width * height) == 0 {
+                text.push('\n');
+            }
+            text.push(match (cell.state, cell.content) {
+                (CellState::Closed, CellContent::Bomb) => '*',
+                (CellState::Closed, _) => '.',
+                (CellState::Flagged, CellContent::Bomb) => 'F',
+                (CellState::Flagged, _) => 'f',
+                (CellState::Exploded, _) => 'X',
+                (CellState::Opened, CellContent::Number(n)) if n > 9 => '+',
+                (CellState::Opened, CellContent::Number(n)) => char::from(b'0' + n),
+                (CellState::Opened, _) => '0',
+            });
+            if (i + 1) % width == 0 {
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+/// creates the minesweeper game which fits the size of the board
+pub fn import_game(
+    text: &str,
+    pos: &Position,
+    player: (Entity, UniqueId),
+) -> Result<Box<dyn CustomGame + Send + Sync>, String> {
+    let board = BoardText::parse(text)?;
+    match (board.width, board.height, board.layers) {
+        (20, 20, 1) => Ok(Box::new(MineSweeperGame::<20>::from_text(
+            &board, pos, player,
+        ))),
+        (10, 10, 10) => Ok(Box::new(MineSweeperGame3d::<10>::from_text(
+            &board, pos, player,
+        ))),
+        (w, h, l) => Err(format!(
+            "there is no game for a {w}x{h} board with {l} layer(s), \
+            boards can be 20x20 or 10x10x10"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::common::fill_numbers;
+
+    fn cells(board: &BoardText, dimension: usize) -> Vec<Cell> {
+        let mut cells: Vec<Cell> = board
+            .cells
+            .iter()
+            .map(|(mine, state)| Cell {
+                content: match mine {
+                    true => CellContent::Bomb,
+                    false => CellContent::Empty,
+                },
+                state: *state,
+                ..Default::default()
+            })
+            .collect();
+        fill_numbers(&mut cells, board.width, dimension);
+        cells
+    }
+
+    #[test]
+    fn round_trip_2d() {
+        let text = "*F.\nf32\n.X1\n";
+        let board = BoardText::parse(text).unwrap();
+        assert_eq!((board.width, board.height, board.layers), (3, 3, 1));
+        assert_eq!(board.cells[0], (true, CellState::Closed));
+        assert_eq!(board.cells[1], (true, CellState::Flagged));
+        assert_eq!(board.cells[3], (false, CellState::Flagged));
+        assert_eq!(board.cells[7], (true, CellState::Exploded));
+        assert_eq!(BoardText::write(&cells(&board, 2), 3, 3), text);
+    }
+
+    #[test]
+    fn round_trip_3d() {
+        let text = "*1\n11\n\n11\n.1\n";
+        let board = BoardText::parse(text).unwrap();
+        assert_eq!((board.width, board.height, board.layers), (2, 2, 2));
+        assert_eq!(BoardText::write(&cells(&board, 3), 2, 2), text);
+    }
+
+    #[test]
+    fn owner_comment() {
+        let text = "# owner 1234\n*.\n..\n";
+        assert_eq!(BoardText::owner(text), Some("1234"));
+        assert_eq!(BoardText::parse(text).unwrap().cells.len(), 4);
+        assert_eq!(BoardText::owner("*.\n..\n"), None);
+    }
+
+    #[test]
+    fn invalid_boards() {
+        let error = |text: &str| BoardText::parse(text).err().unwrap();
+        assert_eq!(error("# only a comment\n"), "the board is empty");
+        assert_eq!(error("..\n.\n"), "row 2 of layer 1 isnt 2 cells wide");
+        assert_eq!(error("..\n..\n\n..\n"), "layer 2 has 1 rows instead of 2");
+        assert_eq!(error("..\n.?\n"), "unknown cell '?'");
+    }
+}
@@ -1,8 +1,32 @@
+use crate::{
+    custom_game::{BuildGameError, Participants, SolverMove},
+    options::GameOptions,
+    postgres_wrapper::{MinesweeperEntry, PostgresWrapper},
+    seed::GameSeed,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
 use valence::{
     prelude::*,
     protocol::{sound::SoundCategory, Sound},
 };
 
+/// time added to the clock for every hint
+pub const HINT_PENALTY: Duration = Duration::from_secs(10);
+/// marks a cell a hint proved to be safe
+pub const HINT_SAFE_BLOCK: BlockState = BlockState::LIME_WOOL;
+/// marks a cell a hint proved to be a mine
+pub const HINT_MINE_BLOCK: BlockState = BlockState::ORANGE_WOOL;
+/// time added to the clock for every mine hit in a game with lives
+pub const MINE_PENALTY: Duration = Duration::from_secs(15);
+/// marks a mine which was hit in a game with lives
+pub const EXPLODED_MINE_BLOCK: BlockState = BlockState::REDSTONE_BLOCK;
+/// marks the mine which ended the game, so it stands out from the ones hit before
+pub const FATAL_MINE_BLOCK: BlockState = BlockState::MAGMA_BLOCK;
+
 #[derive(PartialEq, Copy, Clone, Debug, Default)]
 pub enum CellContent {
     #[default]
@@ -11,12 +35,14 @@ pub enum CellContent {
     Number(u8),
 }
 
-#[derive(PartialEq, Copy, Clone, Default)]
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
 pub enum CellState {
     Opened,
     #[default]
     Closed,
     Flagged,
+    /// a mine that was hit in a game with lives, it counts like a flag
+    Exploded,
 }
 
 #[derive(Default, Copy, Clone, PartialEq)]
@@ -24,6 +50,8 @@ pub struct Cell {
     pub content: CellContent,
     pub state: CellState,
     pub pos: BlockPos,
+    /// the player who placed the flag on this cell
+    pub flagged_by: Option<Entity>,
 }
 
 pub fn sound(layer: &mut ChunkLayer, sound: Sound, loc: &BlockPos) {
@@ -60,3 +88,965 @@ pub fn get_num_color(cell: CellContent) -> BlockState {
         },
     }
 }
+
+/// the time limit of a timed game, larger boards with more mines get more time
+pub fn time_limit(cells: usize, mines: usize) -> Duration {
+    Duration::from_secs_f32(cells as f32 * 0.25 + mines as f32)
+}
+
+/// the block a cell is shown as
+pub fn cell_block(cell: &Cell) -> BlockState {
+    match cell.state {
+        CellState::Closed => BlockState::MOSS_BLOCK,
+        CellState::Opened => get_num_color(cell.content),
+        CellState::Flagged => BlockState::RED_WOOL,
+        CellState::Exploded => EXPLODED_MINE_BLOCK,
+    }
+}
+
+/// shows the result of a lost game: the exploded mine is highlighted, correct flags stay,
+/// wrong flags are marked and all unflagged mines are shown. Other closed cells stay closed
+pub fn reveal_loss(cells: &[Cell], exploded: Option<&BlockPos>, layer: &mut ChunkLayer) {
+    for cell in cells {
+        let block = match (cell.content, cell.state) {
+            (CellContent::Bomb, _) if Some(&cell.pos) == exploded => FATAL_MINE_BLOCK,
+            (CellContent::Bomb, CellState::Flagged | CellState::Exploded) => continue,
+            (CellContent::Bomb, _) => get_num_color(CellContent::Bomb),
+            (_, CellState::Flagged) => BlockState::BLACK_WOOL,
+            _ => continue,
+        };
+        layer.set_block(cell.pos, block);
+    }
+}
+
+/// the standard win condition, every safe cell is opened
+pub fn all_safe_opened(cells: &[Cell]) -> bool {
+    cells
+        .iter()
+        .all(|c| c.state == CellState::Opened || c.content == CellContent::Bomb)
+}
+
+/// the win condition of the flag rule, every mine is flagged or hit and no safe cell is flagged
+pub fn all_mines_flagged(cells: &[Cell]) -> bool {
+    cells.iter().all(|c| match c.content {
+        CellContent::Bomb => matches!(c.state, CellState::Flagged | CellState::Exploded),
+        _ => c.state != CellState::Flagged,
+    })
+}
+
+/// flags every mine which hasnt been flagged or hit yet, used once a game is won
+pub fn flag_all_mines(cells: &mut [Cell], layer: &mut ChunkLayer) {
+    for cell in cells {
+        if cell.content == CellContent::Bomb && cell.state == CellState::Closed {
+            cell.state = CellState::Flagged;
+            layer.set_block(cell.pos, BlockState::RED_WOOL);
+        }
+    }
+}
+
+#[derive(Default, Copy, Clone)]
+pub struct ClickStats {
+    pub left: u32,
+    pub right: u32,
+    pub chord: u32,
+}
+
+impl ClickStats {
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+
+    /// the percentage of clicks that were necessary to solve the board
+    pub fn efficiency(&self, three_bv: usize) -> f32 {
+        if self.total() == 0 {
+            return 0.0;
+        }
+        three_bv as f32 / self.total() as f32 * 100.0
+    }
+}
+
+/// what a single player did in a game, so every player of a cooperative game gets their own stats
+#[derive(Default, Copy, Clone)]
+pub struct Contribution {
+    pub cells_opened: u32,
+    pub correct_flags: u32,
+}
+
+/// counts the correct flags of every player once the game is over,
+/// in cooperative games the contribution of everyone in the team is announced
+pub fn finish_contributions(
+    cells: &[Cell],
+    contributions: &mut HashMap<Entity, Contribution>,
+    team: &[(Entity, UniqueId)],
+    participants: &mut Participants,
+) {
+    for cell in cells {
+        if let (CellContent::Bomb, CellState::Flagged, Some(player)) =
+            (cell.content, cell.state, cell.flagged_by)
+        {
+            contributions.entry(player).or_default().correct_flags += 1;
+        }
+    }
+    if team.len() < 2 {
+        return;
+    }
+    for (player, _) in team {
+        let contribution = contributions.get(player).copied().unwrap_or_default();
+        participants.announce_about(
+            *player,
+            format!(
+                "opened {} cells and placed {} correct flags",
+                contribution.cells_opened, contribution.correct_flags
+            ),
+        );
+    }
+}
+
+/// the neighbours of a cell on a flattened square or cubic board, including the cell itself
+pub fn adjacent_indices(idx: usize, size: usize, dimension: usize) -> Vec<usize> {
+    let mut adjacent = Vec::new();
+    // every combination of -1, 0 and 1 along each axis
+    for offsets in 0..3usize.pow(dimension as u32) {
+        let mut neighbour = 0;
+        let mut inside = true;
+        for axis in (0..dimension).rev() {
+            let coord = (idx / size.pow(axis as u32) % size) as isize
+                + (offsets / 3usize.pow(axis as u32) % 3) as isize
+                - 1;
+            inside &= (0..size as isize).contains(&coord);
+            neighbour = neighbour * size + coord.max(0) as usize;
+        }
+        if inside {
+            adjacent.push(neighbour);
+        }
+    }
+    adjacent
+}
+
+/// Opens a random empty cell and the opening around it before the game starts.
+/// It is chosen with the seeded rng, so everyone playing the same seed starts on the same board
+pub fn open_start(cells: &mut [Cell], size: usize, dimension: usize, rng: &mut StdRng) {
+    let empty: Vec<usize> = (0..cells.len())
+        .filter(|i| cells[*i].content == CellContent::Empty)
+        .collect();
+    let Some(start) = empty.choose(rng).copied() else {
+        return;
+    };
+    cells[start].state = CellState::Opened;
+    let mut stack = vec![start];
+    while let Some(idx) = stack.pop() {
+        if cells[idx].content != CellContent::Empty {
+            continue;
+        }
+        for adj in adjacent_indices(idx, size, dimension) {
+            if cells[adj].state == CellState::Closed {
+                cells[adj].state = CellState::Opened;
+                stack.push(adj);
+            }
+        }
+    }
+}
+
+/// The minimum amount of clicks needed to solve a board:
+/// every opening counts once, plus every number which isnt at the edge of an opening.
+/// Cells which are opened already dont count, e.g. the opening a game starts with
+pub fn three_bv(cells: &[Cell], adjacent: impl Fn(usize) -> Vec<usize>) -> usize {
+    let mut marked: Vec<bool> = cells.iter().map(|c| c.state == CellState::Opened).collect();
+    let mut bv = 0;
+    for start in 0..cells.len() {
+        if marked[start] || cells[start].content != CellContent::Empty {
+            continue;
+        }
+        bv += 1;
+        // flood fill the opening, this also marks the numbers at its edge
+        marked[start] = true;
+        let mut stack = vec![start];
+        while let Some(idx) = stack.pop() {
+            for adj in adjacent(idx) {
+                if marked[adj] || cells[adj].content == CellContent::Bomb {
+                    continue;
+                }
+                marked[adj] = true;
+                if cells[adj].content == CellContent::Empty {
+                    stack.push(adj);
+                }
+            }
+        }
+    }
+    bv + cells
+        .iter()
+        .zip(marked)
+        .filter(|(c, m)| !m && matches!(c.content, CellContent::Number(_)))
+        .count()
+}
+
+pub enum Hint {
+    Safe(usize),
+    Mine(usize),
+}
+
+/// Looks for a cell which is certainly safe or certainly a mine, using only the opened numbers
+/// (flags are ignored as they could be wrong). Every number limits the amount of mines around it,
+/// a number whose unknown neighbours are a subset of another ones decides the difference.
+/// Safe cells are preferred, mines are only returned if they arent flagged or hit yet.
+/// Cells in shown were shown to the player already and are skipped
+pub fn find_hint(
+    cells: &[Cell],
+    adjacent: impl Fn(usize) -> Vec<usize>,
+    shown: &[usize],
+) -> Option<Hint> {
+    let constraints: Vec<(Vec<usize>, usize)> = cells
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, cell)| match (cell.state, cell.content) {
+            (CellState::Opened, CellContent::Number(n)) => Some((
+                adjacent(idx)
+                    .into_iter()
+                    .filter(|a| cells[*a].state != CellState::Opened)
+                    .collect(),
+                n as usize,
+            )),
+            _ => None,
+        })
+        .collect();
+    // true for mines, false for safe cells. Ordered, so replays get the same hints
+    let mut known: BTreeMap<usize, bool> = BTreeMap::new();
+    loop {
+        // the undecided cells of every number and the amount of mines left among them
+        let reduced: Vec<(Vec<usize>, usize)> = constraints
+            .iter()
+            .map(|(unknown, n)| {
+                let mines = unknown
+                    .iter()
+                    .filter(|u| known.get(u) == Some(&true))
+                    .count();
+                let undecided: Vec<usize> = unknown
+                    .iter()
+                    .copied()
+                    .filter(|u| !known.contains_key(u))
+                    .collect();
+                (undecided, n.saturating_sub(mines))
+            })
+            .filter(|(undecided, _)| !undecided.is_empty())
+            .collect();
+        let mut containing: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, (undecided, _)) in reduced.iter().enumerate() {
+            for cell in undecided {
+                containing.entry(*cell).or_default().push(i);
+            }
+        }
+
+        let mut found = Vec::new();
+        for (undecided, mines) in &reduced {
+            if *mines == 0 {
+                found.extend(undecided.iter().map(|c| (*c, false)));
+            } else if *mines == undecided.len() {
+                found.extend(undecided.iter().map(|c| (*c, true)));
+            }
+            // only numbers sharing a cell with this one can contain its undecided cells
+            for other in containing[&undecided[0]].iter().map(|i| &reduced[*i]) {
+                if other.0.len() <= undecided.len()
+                    || !undecided.iter().all(|c| other.0.contains(c))
+                {
+                    continue;
+                }
+                let diff = other.0.iter().filter(|c| !undecided.contains(c));
+                let diff_mines = other.1.saturating_sub(*mines);
+                if diff_mines == 0 {
+                    found.extend(diff.map(|c| (*c, false)));
+                } else if diff_mines == other.0.len() - undecided.len() {
+                    found.extend(diff.map(|c| (*c, true)));
+                }
+            }
+        }
+        if found.is_empty() {
+            break;
+        }
+        known.extend(found);
+        if let Some((idx, _)) = known.iter().find(|(idx, mine)| {
+            !**mine && cells[**idx].state == CellState::Closed && !shown.contains(idx)
+        }) {
+            return Some(Hint::Safe(*idx));
+        }
+    }
+    known
+        .into_iter()
+        .find(|(idx, mine)| *mine && cells[*idx].state == CellState::Closed && !shown.contains(idx))
+        .map(|(idx, _)| Hint::Mine(idx))
+}
+
+/// a random safe cell for hints when nothing can be deduced
+pub fn random_safe_cell(cells: &[Cell], rng: &mut StdRng) -> Option<usize> {
+    let candidates: Vec<usize> = (0..cells.len())
+        .filter(|i| cells[*i].state == CellState::Closed && cells[*i].content != CellContent::Bomb)
+        .collect();
+    candidates.choose(rng).copied()
+}
+
+/// the next move of the auto solver: the cell found by [find_hint] if there is one, otherwise a guess
+pub fn solver_move(cells: &[Cell], hint: Option<Hint>, rng: &mut StdRng) -> Option<SolverMove> {
+    let (idx, flag, guess) = match hint {
+        Some(Hint::Safe(idx)) => (idx, false, false),
+        Some(Hint::Mine(idx)) => (idx, true, false),
+        None => {
+            let closed: Vec<usize> = (0..cells.len())
+                .filter(|i| cells[*i].state == CellState::Closed)
+                .collect();
+            (*closed.choose(rng)?, false, true)
+        }
+    };
+    Some(SolverMove {
+        pos: cells[idx].pos,
+        flag,
+        guess,
+    })
+}
+
+pub fn win_message(three_bv: usize, clicks: &ClickStats, seconds: f32) -> String {
+    format!(
+        "Solved in {:.3}s | 3BV: {} | 3BV/s: {:.2} | Clicks: {} left, {} right, {} chord | Efficiency: {:.0}%",
+        seconds,
+        three_bv,
+        three_bv as f32 / seconds.max(0.05),
+        clicks.left,
+        clicks.right,
+        clicks.chord,
+        clicks.efficiency(three_bv)
+    )
+}
+
+/// Measures a game from its first click with a monotonic clock, so server lag doesnt change
+/// the recorded time. The amount of ticks is counted alongside.
+#[derive(Default)]
+pub struct GameTimer {
+    started: Option<Instant>,
+    stopped: Option<Duration>,
+    ticks: usize,
+    penalty: Duration,
+}
+
+impl GameTimer {
+    /// starts the timer, if it isnt running already
+    pub fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(started) = self.started {
+            self.stopped.get_or_insert(started.elapsed());
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some() && self.stopped.is_none()
+    }
+
+    pub fn tick(&mut self) {
+        if self.is_running() {
+            self.ticks += 1;
+        }
+    }
+
+    /// the time added on top of the measured time, e.g. for hints
+    pub fn add_penalty(&mut self, penalty: Duration) {
+        self.penalty += penalty;
+        self.ticks += (penalty.as_millis() / 50) as usize;
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.stopped
+            .or(self.started.map(|s| s.elapsed()))
+            .unwrap_or_default()
+            + self.penalty
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    /// the time counted in ticks, penalties included. Unlike [GameTimer::elapsed] it doesnt depend
+    /// on how fast the server or a replay runs, so rules based on it play out the same every time
+    pub fn tick_time(&self) -> Duration {
+        Duration::from_millis(self.ticks as u64 * 50)
+    }
+}
+
+/// places mines randomly until there are enough of them
+pub fn place_mines(cells: &mut [Cell], bomb_amt: usize, rng: &mut StdRng) {
+    while cells
+        .iter()
+        .filter(|c| c.content == CellContent::Bomb)
+        .count()
+        < bomb_amt
+    {
+        for cell in cells.iter_mut() {
+            if rng.gen_ratio(1, 100) {
+                cell.content = CellContent::Bomb;
+            }
+        }
+    }
+}
+
+/// gives every safe cell next to a mine the amount of mines around it
+pub fn fill_numbers(cells: &mut [Cell], size: usize, dimension: usize) {
+    for idx in 0..cells.len() {
+        if cells[idx].content == CellContent::Bomb {
+            continue;
+        }
+        let bombs = adjacent_indices(idx, size, dimension)
+            .into_iter()
+            .filter(|a| cells[*a].content == CellContent::Bomb)
+            .count();
+        cells[idx].content = match bombs {
+            0 => CellContent::Empty,
+            n => CellContent::Number(n as u8),
+        };
+    }
+}
+
+/// The rules and statistics the 2D and 3D games share. The games only know where their cells are,
+/// they hand them to it flattened and the size and dimension tell which cells are neighbours
+pub struct MinesweeperCore {
+    size: usize,
+    dimension: usize,
+    bomb_amt: usize,
+    pub participants: Participants,
+    pub seed: GameSeed,
+    rng: StdRng,
+    pub should_despawn: bool,
+    pub is_over: bool,
+    pub is_won: bool,
+    timer: GameTimer,
+    clicks: ClickStats,
+    three_bv: usize,
+    contributions: HashMap<Entity, Contribution>,
+    /// the owner and everyone who interacted with the game, even if they left before it ended
+    team: Vec<(Entity, UniqueId)>,
+    hints: u32,
+    /// the cells hints have highlighted, the next hint has to tell something new
+    hinted: Vec<usize>,
+    /// the game is lost once all lives are used up, standard games have a single life
+    lives: u32,
+    mistakes: u32,
+    /// imported boards dont start with an opening and their results arent saved
+    imported: bool,
+    /// timed games are lost once the ticks of the timer reach the limit
+    time_limit: Option<Duration>,
+    /// flagging exactly all mines wins the game as well
+    flag_win: bool,
+}
+
+impl MinesweeperCore {
+    /// opens the starting opening of the cells, the rng has to be the one the board was generated with
+    pub fn new(
+        cells: &mut [Cell],
+        (size, dimension): (usize, usize),
+        bomb_amt: usize,
+        player: (Entity, UniqueId),
+        seed: GameSeed,
+        mut rng: StdRng,
+    ) -> MinesweeperCore {
+        open_start(cells, size, dimension, &mut rng);
+        MinesweeperCore {
+            size,
+            dimension,
+            bomb_amt,
+            participants: Participants::new(player),
+            seed,
+            rng,
+            should_despawn: false,
+            is_over: false,
+            is_won: false,
+            timer: GameTimer::default(),
+            clicks: ClickStats::default(),
+            three_bv: three_bv(cells, |i| adjacent_indices(i, size, dimension)),
+            contributions: HashMap::new(),
+            team: vec![player],
+            hints: 0,
+            hinted: Vec::new(),
+            lives: 1,
+            mistakes: 0,
+            imported: false,
+            time_limit: None,
+            flag_win: false,
+        }
+    }
+
+    /// the cells were replaced by a board loaded from text
+    pub fn import(&mut self, cells: &[Cell]) {
+        self.three_bv = three_bv(cells, |i| self.adjacent(i));
+        self.imported = true;
+    }
+
+    /// applies the options of casual games with lives, timed games and the flag rule
+    pub fn with_options(&mut self, options: &GameOptions) {
+        self.lives = options.lives.unwrap_or(1).max(1);
+        self.flag_win = options.flag_win;
+        if options.timed {
+            let limit = time_limit(self.size.pow(self.dimension as u32), self.bomb_amt);
+            self.time_limit = Some(limit);
+            self.participants
+                .announce(format!("Clear the board within {}s", limit.as_secs()));
+        }
+    }
+
+    /// remembers a player who interacted with the game as part of the team
+    fn join_team(&mut self, player: Entity) {
+        if self.team.iter().any(|p| p.0 == player) {
+            return;
+        }
+        if let Some(p) = self.participants.players().find(|p| p.0 == player) {
+            self.team.push(p);
+        }
+    }
+
+    /// the time the game took, timed games are measured in ticks like their time limit,
+    /// so a game won in time never has a time above the limit
+    fn time(&self) -> Duration {
+        match self.time_limit {
+            Some(_) => self.timer.tick_time(),
+            None => self.timer.elapsed(),
+        }
+    }
+
+    /// a penalty can use up the time before the next tick ends the game
+    fn out_of_time(&self) -> bool {
+        self.time_limit
+            .is_some_and(|limit| self.timer.tick_time() >= limit)
+    }
+
+    fn adjacent(&self, idx: usize) -> Vec<usize> {
+        adjacent_indices(idx, self.size, self.dimension)
+    }
+
+    /// the cell in the middle of the board, sounds about the whole game are played there
+    fn center(&self) -> usize {
+        (0..self.dimension)
+            .map(|axis| self.size / 2 * self.size.pow(axis as u32))
+            .sum()
+    }
+
+    /// opens a cell, the game is lost or won if it was the last one
+    pub fn open(&mut self, cells: &mut [Cell], idx: usize, player: Entity, layer: &mut ChunkLayer) {
+        self.open_cell(cells, idx, player, layer);
+        self.check_win(cells, layer);
+    }
+
+    fn open_cell(
+        &mut self,
+        cells: &mut [Cell],
+        idx: usize,
+        player: Entity,
+        layer: &mut ChunkLayer,
+    ) {
+        if cells[idx].state != CellState::Closed {
+            return;
+        }
+        let pos = cells[idx].pos;
+        match cells[idx].content {
+            CellContent::Bomb => {
+                sound(layer, Sound::EntityGenericExplode, &pos);
+                self.mistakes += 1;
+                if self.mistakes < self.lives {
+                    // the mine is marked and the game goes on, at the cost of a life
+                    cells[idx].state = CellState::Exploded;
+                    layer.set_block(pos, EXPLODED_MINE_BLOCK);
+                    self.timer.add_penalty(MINE_PENALTY);
+                    self.participants.announce_about(
+                        player,
+                        format!(
+                            "hit a mine, {} lives left (+{}s)",
+                            self.lives - self.mistakes,
+                            MINE_PENALTY.as_secs()
+                        ),
+                    );
+                    return;
+                }
+                self.timer.stop();
+                reveal_loss(cells, Some(&pos), layer);
+                self.is_over = true;
+                if self.team.len() > 1 {
+                    self.participants.announce_about(player, "set off a mine");
+                }
+                finish_contributions(
+                    cells,
+                    &mut self.contributions,
+                    &self.team,
+                    &mut self.participants,
+                );
+            }
+            CellContent::Empty => {
+                sound(layer, Sound::EntityFrogStep, &pos);
+                cells[idx].state = CellState::Opened;
+                self.contributions.entry(player).or_default().cells_opened += 1;
+                layer.set_block(pos, get_num_color(cells[idx].content));
+                for adj in self.adjacent(idx) {
+                    // simulate click on adjacent empty fields
+                    self.open_cell(cells, adj, player, layer);
+                }
+            }
+            CellContent::Number(_) => {
+                sound(layer, Sound::EntityFrogStep, &pos);
+                cells[idx].state = CellState::Opened;
+                self.contributions.entry(player).or_default().cells_opened += 1;
+                let b = layer.set_block(pos, get_num_color(cells[idx].content));
+                if !(b.clone().is_some_and(|b| {
+                    b.state == BlockState::MOSS_BLOCK || b.state == HINT_SAFE_BLOCK
+                })) {
+                    tracing::error!(
+                        "something went wrong clicking a minesweeper field, replaced block: {:?}",
+                        b.map(|b| b.state)
+                    );
+                }
+            }
+        }
+    }
+
+    /// ends the game as won once every safe cell is opened,
+    /// or with the flag rule once exactly all mines are flagged
+    fn check_win(&mut self, cells: &mut [Cell], layer: &mut ChunkLayer) {
+        if self.is_over
+            || self.out_of_time()
+            || !(all_safe_opened(cells) || (self.flag_win && all_mines_flagged(cells)))
+        {
+            return;
+        }
+        sound(layer, Sound::ItemGoatHornSound1, &cells[self.center()].pos);
+        flag_all_mines(cells, layer);
+        self.is_over = true;
+        self.is_won = true;
+        self.timer.stop();
+        self.participants.announce(win_message(
+            self.three_bv,
+            &self.clicks,
+            self.time().as_secs_f32(),
+        ));
+        finish_contributions(
+            cells,
+            &mut self.contributions,
+            &self.team,
+            &mut self.participants,
+        );
+    }
+
+    /// opens all closed neighbours of an opened number, if it has the right amount of flags around it
+    fn chord(&mut self, cells: &mut [Cell], idx: usize, player: Entity, layer: &mut ChunkLayer) {
+        let CellContent::Number(n) = cells[idx].content else {
+            return;
+        };
+        let adjacent = self.adjacent(idx);
+        if adjacent
+            .iter()
+            .filter(|a| matches!(cells[**a].state, CellState::Flagged | CellState::Exploded))
+            .count()
+            != n as usize
+        {
+            return;
+        }
+        // a chord over a wrong flag always opens a mine, the player who placed the flag gets the blame for it,
+        // the cells it opens are still credited to the player who chorded
+        let blamed = adjacent
+            .iter()
+            .map(|a| cells[*a])
+            .find(|c| c.state == CellState::Flagged && c.content != CellContent::Bomb)
+            .and_then(|c| c.flagged_by)
+            .unwrap_or(player);
+        for adj in adjacent {
+            if !self.is_over && cells[adj].state == CellState::Closed {
+                let opener = match cells[adj].content {
+                    CellContent::Bomb => blamed,
+                    _ => player,
+                };
+                self.open(cells, adj, opener, layer);
+            }
+        }
+    }
+
+    /// returns false if the click didnt hit a cell of a running game
+    pub fn click_left(
+        &mut self,
+        cells: &mut [Cell],
+        click_pos: &BlockPos,
+        player: Entity,
+        layer: &mut ChunkLayer,
+    ) -> bool {
+        if self.is_over {
+            self.should_despawn = true;
+            return false;
+        }
+        let Some(idx) = cells.iter().position(|c| c.pos == *click_pos) else {
+            return false;
+        };
+        self.join_team(player);
+        self.timer.start();
+        if cells[idx].state == CellState::Opened {
+            self.clicks.chord += 1;
+            self.chord(cells, idx, player, layer);
+        } else {
+            self.clicks.left += 1;
+            self.open(cells, idx, player, layer);
+        }
+        return true;
+    }
+
+    /// returns false if the click didnt hit a cell of a running game
+    pub fn click_right(
+        &mut self,
+        cells: &mut [Cell],
+        click_pos: &BlockPos,
+        player: Entity,
+        layer: &mut ChunkLayer,
+    ) -> bool {
+        if self.is_over {
+            return false;
+        }
+        let Some(cell) = cells.iter_mut().find(|c| c.pos == *click_pos) else {
+            return false;
+        };
+        self.join_team(player);
+        self.timer.start();
+        match cell.state {
+            CellState::Closed => {
+                self.clicks.right += 1;
+                layer.set_block(cell.pos, BlockState::RED_WOOL);
+                cell.state = CellState::Flagged;
+                cell.flagged_by = Some(player);
+            }
+            CellState::Flagged => {
+                self.clicks.right += 1;
+                layer.set_block(cell.pos, BlockState::MOSS_BLOCK);
+                cell.state = CellState::Closed;
+                cell.flagged_by = None;
+            }
+            _ => (),
+        }
+        self.check_win(cells, layer);
+        return true;
+    }
+
+    /// counts the time and ends timed games once their time is up
+    pub fn tick(&mut self, cells: &[Cell], layer: &mut ChunkLayer) {
+        self.timer.tick();
+        let Some(limit) = self.time_limit else {
+            return;
+        };
+        if !self.timer.is_running() {
+            return;
+        }
+        let remaining = limit.saturating_sub(self.timer.tick_time());
+        if remaining.is_zero() {
+            sound(
+                layer,
+                Sound::EntityGenericExplode,
+                &cells[self.center()].pos,
+            );
+            self.timer.stop();
+            reveal_loss(cells, None, layer);
+            self.is_over = true;
+            self.participants.show_status("Time is up!");
+            self.participants.announce("Time is up!");
+            finish_contributions(
+                cells,
+                &mut self.contributions,
+                &self.team,
+                &mut self.participants,
+            );
+        } else if self.timer.ticks() % 10 == 0 {
+            self.participants
+                .show_status(format!("{:.1}s left", remaining.as_secs_f32()));
+        }
+    }
+
+    pub fn hint(&mut self, cells: &mut [Cell], player: Entity, layer: &mut ChunkLayer) -> bool {
+        if self.is_over {
+            return false;
+        }
+        self.join_team(player);
+        self.timer.start();
+        match find_hint(cells, |i| self.adjacent(i), &self.hinted) {
+            Some(Hint::Safe(idx)) => {
+                layer.set_block(cells[idx].pos, HINT_SAFE_BLOCK);
+                self.hinted.push(idx);
+            }
+            Some(Hint::Mine(idx)) => {
+                layer.set_block(cells[idx].pos, HINT_MINE_BLOCK);
+                self.hinted.push(idx);
+            }
+            // nothing can be deduced, so a random safe cell is opened instead
+            None => match random_safe_cell(cells, &mut self.rng) {
+                Some(idx) => self.open(cells, idx, player, layer),
+                None => return false,
+            },
+        }
+        self.hints += 1;
+        self.timer.add_penalty(HINT_PENALTY);
+        self.participants
+            .announce_about(player, format!("used a hint, +{}s", HINT_PENALTY.as_secs()));
+        return true;
+    }
+
+    pub fn solver_move(&mut self, cells: &[Cell]) -> Option<SolverMove> {
+        if self.is_over {
+            return None;
+        }
+        // the solver clicks the cells it finds, so it doesnt need to skip any
+        let hint = find_hint(cells, |i| self.adjacent(i), &[]);
+        solver_move(cells, hint, &mut self.rng)
+    }
+
+    pub fn save(&self, pgsql: &mut PostgresWrapper) {
+        if !self.is_won || self.imported {
+            return;
+        }
+        let seconds = self.time().as_secs_f32();
+        // the whole team shares the result, the contribution is stored for every player
+        for (player, uuid) in self.team.iter().copied() {
+            let contribution = self.contributions.get(&player).copied().unwrap_or_default();
+            pgsql.insert_minesweeper(MinesweeperEntry {
+                size: self.size as i32,
+                dimension: self.dimension as i32,
+                comp_time: self.timer.ticks() as i32,
+                comp_time_ms: self.time().as_millis() as i32,
+                bomb_amt: self.bomb_amt as i32,
+                three_bv: self.three_bv as i32,
+                left_clicks: self.clicks.left as i32,
+                right_clicks: self.clicks.right as i32,
+                chord_clicks: self.clicks.chord as i32,
+                efficiency: self.clicks.efficiency(self.three_bv),
+                three_bv_per_sec: self.three_bv as f32 / seconds.max(0.05),
+                seed: self.seed,
+                team_size: self.team.len() as i32,
+                hints: self.hints as i32,
+                lives: self.lives as i32,
+                mistakes: self.mistakes as i32,
+                time_limit_ms: self.time_limit.map(|l| l.as_millis() as i32),
+                flag_win: self.flag_win,
+                cells_opened: contribution.cells_opened as i32,
+                correct_flags: contribution.correct_flags as i32,
+                uuid,
+            });
+        }
+    }
+}
+
+/// builds the cells, if there is room for them
+pub fn build_cells(cells: &[Cell], layer: &mut ChunkLayer) -> Result<(), BuildGameError> {
+    if cells
+        .iter()
+        .any(|c| layer.block(c.pos).is_some_and(|b| !b.state.is_air()))
+    {
+        return Err(BuildGameError::BlocksInTheWay);
+    }
+    for cell in cells {
+        layer.set_block(cell.pos, cell_block(cell));
+    }
+    return Ok(());
+}
+
+/// removes the blocks of the cells
+pub fn reset_cells(cells: &[Cell], layer: &mut ChunkLayer) {
+    for cell in cells {
+        layer.set_block(cell.pos, BlockState::AIR);
+    }
+}
+
+/// how many of the safe cells are opened, from 0.0 to 1.0
+pub fn progress(cells: &[Cell]) -> f32 {
+    let safe_cells = cells
+        .iter()
+        .filter(|c| c.content != CellContent::Bomb)
+        .count();
+    let opened = cells
+        .iter()
+        .filter(|c| c.state == CellState::Opened)
+        .count();
+    opened as f32 / safe_cells as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(size: usize, dimension: usize, mines: &[usize]) -> Vec<Cell> {
+        let mut cells = vec![Cell::default(); size.pow(dimension as u32)];
+        for mine in mines {
+            cells[*mine].content = CellContent::Bomb;
+        }
+        fill_numbers(&mut cells, size, dimension);
+        cells
+    }
+
+    #[test]
+    fn three_bv_counts_openings_and_lone_numbers() {
+        // two openings in the free corners cover every number
+        let cells = board(3, 2, &[0, 8]);
+        assert_eq!(three_bv(&cells, |i| adjacent_indices(i, 3, 2)), 2);
+        // no openings, every number needs its own click
+        let cells = board(3, 2, &[0, 2, 6, 8]);
+        assert_eq!(three_bv(&cells, |i| adjacent_indices(i, 3, 2)), 5);
+        // every cell of the cube touches the mine in its center
+        let cells = board(3, 3, &[13]);
+        assert_eq!(three_bv(&cells, |i| adjacent_indices(i, 3, 3)), 26);
+    }
+
+    #[test]
+    fn three_bv_skips_opened_cells() {
+        let mut cells = board(3, 2, &[0, 8]);
+        // the opening in the top right corner and its numbers are opened already
+        for idx in [1, 2, 4, 5] {
+            cells[idx].state = CellState::Opened;
+        }
+        assert_eq!(three_bv(&cells, |i| adjacent_indices(i, 3, 2)), 1);
+
+        let mut cells = board(3, 2, &[0, 2, 6, 8]);
+        cells[4].state = CellState::Opened;
+        assert_eq!(three_bv(&cells, |i| adjacent_indices(i, 3, 2)), 4);
+    }
+
+    #[test]
+    fn find_hint_deduces_safe_cells() {
+        let mut cells = board(3, 2, &[0]);
+        // the mine is in one of the two top cells of the left column, so the bottom one is safe
+        for idx in [1, 2, 4, 5, 7, 8] {
+            cells[idx].state = CellState::Opened;
+        }
+        let hint = find_hint(&cells, |i| adjacent_indices(i, 3, 2), &[]);
+        assert!(matches!(hint, Some(Hint::Safe(6))));
+    }
+
+    #[test]
+    fn find_hint_deduces_mines() {
+        let mut cells = board(3, 2, &[0]);
+        for cell in &mut cells[1..] {
+            cell.state = CellState::Opened;
+        }
+        let hint = find_hint(&cells, |i| adjacent_indices(i, 3, 2), &[]);
+        assert!(matches!(hint, Some(Hint::Mine(0))));
+        // flagged mines arent shown again
+        cells[0].state = CellState::Flagged;
+        assert!(find_hint(&cells, |i| adjacent_indices(i, 3, 2), &[]).is_none());
+    }
+
+    #[test]
+    fn find_hint_skips_shown_cells() {
+        let mut cells = board(3, 2, &[0]);
+        for idx in [1, 2, 3, 4, 5, 7, 8] {
+            cells[idx].state = CellState::Opened;
+        }
+        let hint = find_hint(&cells, |i| adjacent_indices(i, 3, 2), &[]);
+        assert!(matches!(hint, Some(Hint::Safe(6))));
+        let hint = find_hint(&cells, |i| adjacent_indices(i, 3, 2), &[6]);
+        assert!(matches!(hint, Some(Hint::Mine(0))));
+        assert!(find_hint(&cells, |i| adjacent_indices(i, 3, 2), &[6, 0]).is_none());
+    }
+
+    #[test]
+    fn find_hint_needs_a_deduction() {
+        let mut cells = board(3, 2, &[0]);
+        // the mine could be any of the closed cells around the center
+        cells[4].state = CellState::Opened;
+        assert!(find_hint(&cells, |i| adjacent_indices(i, 3, 2), &[]).is_none());
+    }
+}
@@ -0,0 +1,163 @@
+use super::MineSweeperGame;
+use crate::{
+    custom_game::{CustomGame, CustomGameContainer, InputLock},
+    seed::GameSeed,
+};
+use valence::prelude::*;
+
+const DEMO_SIZE: usize = 20;
+/// a fresh board is shown for a moment before the solver starts
+const DEMO_START_TICKS: u32 = 20;
+/// how long a finished board stays before the next one is generated
+const DEMO_PAUSE_TICKS: u32 = 20 * 3;
+/// placed above the cells the solver had to guess
+const GUESS_MARKER: BlockState = BlockState::YELLOW_CARPET;
+
+pub struct DemoPlugin;
+
+impl Plugin for DemoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DemoEvent>()
+            .add_systems(Update, (start_demos, run_demos));
+    }
+}
+
+/// starts a minesweeper game which plays itself at the position of the player,
+/// changes the speed of the players demo if it already runs, or stops it if the speed is None
+#[derive(Event)]
+pub struct DemoEvent {
+    pub player: Entity,
+    /// moves per tick
+    pub speed: Option<f32>,
+}
+
+/// A game played by the solver, it gets no inputs from players.
+/// Once the game is over a new board is generated at the same place
+#[derive(Component)]
+struct SolverDemo {
+    speed: f32,
+    progress: f32,
+    ticks: u32,
+    finished_ticks: u32,
+    guesses: Vec<BlockPos>,
+}
+
+impl SolverDemo {
+    fn new(speed: f32) -> SolverDemo {
+        SolverDemo {
+            speed,
+            progress: 0.0,
+            ticks: 0,
+            finished_ticks: 0,
+            guesses: Vec::new(),
+        }
+    }
+
+    fn remove_guess_markers(&mut self, layer: &mut ChunkLayer) {
+        for guess in self.guesses.drain(..) {
+            layer.set_block(guess.offset(0, 1, 0), BlockState::AIR);
+        }
+    }
+}
+
+fn spawn_demo(pos: &Position, owner: (Entity, UniqueId), speed: f32, commands: &mut Commands) {
+    let game = MineSweeperGame::<DEMO_SIZE>::new(pos, owner, GameSeed::random());
+    commands.spawn((
+        CustomGameContainer(Box::new(game)),
+        SolverDemo::new(speed),
+        InputLock,
+    ));
+}
+
+fn remove_demo(
+    entity: Entity,
+    game: &CustomGameContainer,
+    demo: &mut SolverDemo,
+    layer: &mut ChunkLayer,
+    commands: &mut Commands,
+) {
+    demo.remove_guess_markers(layer);
+    game.reset(layer);
+    commands.entity(entity).despawn();
+}
+
+fn start_demos(
+    mut demo_events: EventReader<DemoEvent>,
+    players: Query<(&Position, &UniqueId)>,
+    mut demos: Query<(Entity, &CustomGameContainer, &mut SolverDemo)>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut commands: Commands,
+) {
+    for event in demo_events.iter() {
+        let layer = layers.single_mut().into_inner();
+        let mut running = false;
+        for (entity, game, mut demo) in demos.iter_mut() {
+            if game.participants().owner().0 != event.player {
+                continue;
+            }
+            match event.speed {
+                Some(speed) => {
+                    demo.speed = speed;
+                    running = true;
+                }
+                None => remove_demo(entity, game, &mut demo, layer, &mut commands),
+            }
+        }
+        if running {
+            continue;
+        }
+        if let (Some(speed), Ok((pos, uuid))) = (event.speed, players.get(event.player)) {
+            spawn_demo(pos, (event.player, *uuid), speed, &mut commands);
+        }
+    }
+}
+
+fn run_demos(
+    mut demos: Query<(Entity, &mut CustomGameContainer, &mut SolverDemo)>,
+    players: Query<(), With<Client>>,
+    mut layers: Query<&mut ChunkLayer>,
+    mut commands: Commands,
+) {
+    for (entity, mut game, mut demo) in demos.iter_mut() {
+        let demo = &mut *demo;
+        let layer = layers.single_mut().into_inner();
+        let owner = game.participants().owner();
+        // nobody is left to watch it
+        if !players.contains(owner.0) {
+            remove_demo(entity, &game, demo, layer, &mut commands);
+            continue;
+        }
+
+        if game.is_over() {
+            demo.finished_ticks += 1;
+            if demo.finished_ticks > DEMO_PAUSE_TICKS {
+                let origin = game.origin();
+                let pos = Position::new([origin.x as f64, origin.y as f64, origin.z as f64]);
+                remove_demo(entity, &game, demo, layer, &mut commands);
+                spawn_demo(&pos, owner, demo.speed, &mut commands);
+            }
+            continue;
+        }
+
+        demo.ticks += 1;
+        if demo.ticks <= DEMO_START_TICKS {
+            continue;
+        }
+        demo.progress += demo.speed;
+        while demo.progress >= 1.0 && !game.is_over() {
+            demo.progress -= 1.0;
+            let Some(solver_move) = game.solver_move() else {
+                break;
+            };
+            if solver_move.guess {
+                demo.guesses.push(solver_move.pos);
+                layer.set_block(solver_move.pos.offset(0, 1, 0), GUESS_MARKER);
+            }
+            if solver_move.flag {
+                game.click_right(&solver_move.pos, owner.0, layer);
+            } else {
+                game.click_left(&solver_move.pos, owner.0, layer);
+            }
+        }
+    }
+}
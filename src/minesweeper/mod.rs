@@ -1,5 +1,11 @@
+mod board_text;
 mod common;
+mod demo;
 mod minesweeper;
 mod minesweeper3d;
+mod race;
+pub use board_text::{import_game, BoardText};
+pub use demo::{DemoEvent, DemoPlugin};
 pub use minesweeper::MineSweeperGame;
 pub use minesweeper3d::MineSweeperGame3d;
+pub use race::{RacePlugin, StartRaceEvent};
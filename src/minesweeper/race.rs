@@ -0,0 +1,211 @@
+use std::time::{Duration, Instant};
+
+use super::MineSweeperGame;
+use crate::{
+    custom_game::{CustomGame, CustomGameContainer, GameRecording, HintLock, InputLock},
+    options::GameOptions,
+    postgres_wrapper::PostgresWrapper,
+    seed::GameSeed,
+};
+use valence::{prelude::*, title::SetTitle};
+
+const RACE_SIZE: usize = 20;
+/// space between the boards of the racers
+const BOARD_GAP: usize = 3;
+const COUNTDOWN_TICKS: usize = 20 * 5;
+/// how often the progress of all racers is shown
+const COMPARISON_INTERVAL: usize = 10;
+
+pub struct RacePlugin;
+
+impl Plugin for RacePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StartRaceEvent>()
+            .add_systems(Update, (start_races, run_races));
+    }
+}
+
+/// starts a minesweeper race between the players, the first player hosts it
+#[derive(Event)]
+pub struct StartRaceEvent {
+    pub players: Vec<Entity>,
+}
+
+/// Every racer plays on their own board, all generated from the same seed.
+/// The boards start with the same opening, which is shown during the countdown,
+/// so every racer plays the exact same board no matter where they click first
+#[derive(Component)]
+struct Race {
+    seed: GameSeed,
+    racers: Vec<Racer>,
+    countdown: usize,
+    started: Option<Instant>,
+    ticks: usize,
+}
+
+struct Racer {
+    game: Entity,
+    player: (Entity, UniqueId),
+    /// the time since the start of the race at which the board was cleared
+    finished: Option<Duration>,
+    /// lost or left the race without clearing the board
+    out: bool,
+}
+
+fn start_races(
+    mut start_races: EventReader<StartRaceEvent>,
+    mut players: Query<(&mut Position, &UniqueId)>,
+    mut commands: Commands,
+) {
+    for event in start_races.iter() {
+        let Some(host_pos) = event
+            .players
+            .first()
+            .and_then(|host| players.get(*host).ok())
+            .map(|p| p.0 .0)
+        else {
+            continue;
+        };
+        let seed = GameSeed::random();
+        let mut racers = Vec::new();
+        for (i, player) in event.players.iter().enumerate() {
+            let Ok((mut pos, uuid)) = players.get_mut(*player) else {
+                continue;
+            };
+            // the boards are built next to each other, starting at the host
+            let board_pos = Position::new(
+                host_pos + DVec3::new((i * (RACE_SIZE + BOARD_GAP)) as f64, 0.0, 0.0),
+            );
+            let mut game = MineSweeperGame::<RACE_SIZE>::new(&board_pos, (*player, *uuid), seed);
+            game.participants_mut().close();
+            game.participants_mut()
+                .announce("The race starts in 5 seconds, everyone gets the same board");
+            let recording =
+                GameRecording::new("minesweeper", game.seed(), 0.0, GameOptions::default());
+            let game = commands
+                .spawn((
+                    CustomGameContainer(Box::new(game)),
+                    recording,
+                    InputLock,
+                    HintLock,
+                ))
+                .id();
+            racers.push(Racer {
+                game,
+                player: (*player, *uuid),
+                finished: None,
+                out: false,
+            });
+
+            let center = (RACE_SIZE / 2) as f64;
+            pos.set(board_pos.0 + DVec3::new(center, 1.0, center));
+        }
+        commands.spawn(Race {
+            seed,
+            racers,
+            countdown: COUNTDOWN_TICKS,
+            started: None,
+            ticks: 0,
+        });
+    }
+}
+
+fn run_races(
+    mut races: Query<(Entity, &mut Race)>,
+    games: Query<&CustomGameContainer>,
+    mut clients: Query<(&mut Client, &Username)>,
+    mut commands: Commands,
+    database: Local<PostgresWrapper>,
+) {
+    for (race_entity, mut race) in races.iter_mut() {
+        let race = &mut *race;
+
+        let Some(started) = race.started else {
+            if race.countdown % 20 == 0 {
+                let title = match race.countdown / 20 {
+                    0 => "Go!".to_string(),
+                    n => n.to_string(),
+                };
+                for racer in &race.racers {
+                    if let Ok((mut client, _)) = clients.get_mut(racer.player.0) {
+                        client.set_title(title.clone());
+                    }
+                }
+            }
+            if race.countdown == 0 {
+                race.started = Some(Instant::now());
+                for racer in &race.racers {
+                    commands.entity(racer.game).remove::<InputLock>();
+                }
+            } else {
+                race.countdown -= 1;
+            }
+            continue;
+        };
+
+        race.ticks += 1;
+        // a board only counts as finished once it is won, boards which were despawned before are out,
+        // boards cleared in the same tick get the same time
+        let elapsed = started.elapsed();
+        for racer in race.racers.iter_mut() {
+            if racer.finished.is_some() || racer.out {
+                continue;
+            }
+            match games.get(racer.game) {
+                Ok(game) if game.is_won() => racer.finished = Some(elapsed),
+                Ok(game) => racer.out = game.is_over(),
+                Err(_) => racer.out = true,
+            }
+        }
+        let name = |player: Entity| {
+            clients
+                .get(player)
+                .map_or("unknown".to_string(), |c| c.1 .0.clone())
+        };
+
+        let mut announcement = None;
+        let winner = race
+            .racers
+            .iter()
+            .filter_map(|r| r.finished.map(|time| (r, time)))
+            .min_by_key(|(_, time)| *time);
+        if let Some((winner, time)) = winner {
+            announcement = Some(format!(
+                "{} won the race in {:.3}s!",
+                name(winner.player.0),
+                time.as_secs_f32()
+            ));
+            database.insert_race(
+                &race.seed,
+                RACE_SIZE as i32,
+                race.racers.len() as i32,
+                winner.player.1,
+                time.as_millis() as i32,
+            );
+        } else if race.racers.iter().all(|r| r.out) {
+            announcement = Some("Nobody cleared their board, the race is over".to_string());
+        }
+
+        if let Some(msg) = announcement {
+            for racer in &race.racers {
+                if let Ok((mut client, _)) = clients.get_mut(racer.player.0) {
+                    client.send_chat_message(msg.clone());
+                }
+            }
+            commands.entity(race_entity).despawn();
+        } else if race.ticks % COMPARISON_INTERVAL == 0 {
+            let comparison = race
+                .racers
+                .iter()
+                .filter_map(|r| games.get(r.game).ok().map(|g| (r, g.progress())))
+                .map(|(r, progress)| format!("{} {:.0}%", name(r.player.0), progress * 100.0))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            for racer in &race.racers {
+                if let Ok((mut client, _)) = clients.get_mut(racer.player.0) {
+                    client.set_action_bar(comparison.clone());
+                }
+            }
+        }
+    }
+}
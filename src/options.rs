@@ -0,0 +1,155 @@
+use crate::{
+    items::StartItemType,
+    repeat_sequence::{
+        self, SequenceDisplay, SequenceLayout, SequenceMode, SequenceSpeed, SequenceVariant,
+    },
+};
+use std::fmt::Display;
+
+/// Rules a game can be started with besides the standard ones, written as `name=value` or `name` in chat.
+/// Options which dont apply to a game are rejected with [GameOptions::check_game]
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct GameOptions {
+    /// casual minesweeper: hitting a mine costs a life instead of ending the game
+    pub lives: Option<u32>,
+    /// beat the clock minesweeper: the game is lost once its time limit runs out
+    pub timed: bool,
+    /// minesweeper: flagging exactly all mines wins, not only opening every safe cell
+    pub flag_win: bool,
+    /// repeat sequence: how fast the sequence is shown
+    pub speed: SequenceSpeed,
+    /// repeat sequence: how many clicks can be missed and how long an input may take
+    pub sequence_mode: SequenceMode,
+    /// repeat sequence: if the steps are shown, played as notes or both
+    pub sequence_display: SequenceDisplay,
+    /// repeat sequence: in which order the sequence has to be entered
+    pub sequence_variant: SequenceVariant,
+    /// repeat sequence: buttons on a wall or floor tiles to walk onto
+    pub sequence_layout: SequenceLayout,
+    /// repeat sequence: the width and height of the grid, instead of the size of the game
+    pub sequence_size: Option<(usize, usize)>,
+}
+
+impl GameOptions {
+    /// parses options like `lives=3 timed speed=fast`, returns the offending argument if one is invalid
+    pub fn parse<'a>(args: impl IntoIterator<Item = &'a str>) -> Result<GameOptions, String> {
+        let mut options = GameOptions::default();
+        for arg in args {
+            let (name, value) = arg.split_once('=').unwrap_or((arg, ""));
+            match name {
+                "lives" => {
+                    let lives = value
+                        .parse()
+                        .ok()
+                        .filter(|l| (1..=10).contains(l))
+                        .ok_or(arg.to_string())?;
+                    // a single life is the standard game
+                    options.lives = (lives > 1).then_some(lives);
+                }
+                "timed" if value.is_empty() => options.timed = true,
+                "flagwin" if value.is_empty() => options.flag_win = true,
+                "speed" => {
+                    options.speed = SequenceSpeed::from_name(value).ok_or(arg.to_string())?;
+                }
+                "mode" => {
+                    options.sequence_mode =
+                        SequenceMode::from_name(value).ok_or(arg.to_string())?;
+                }
+                "display" => {
+                    options.sequence_display =
+                        SequenceDisplay::from_name(value).ok_or(arg.to_string())?;
+                }
+                "variant" => {
+                    options.sequence_variant =
+                        SequenceVariant::from_name(value).ok_or(arg.to_string())?;
+                }
+                "layout" => {
+                    options.sequence_layout =
+                        SequenceLayout::from_name(value).ok_or(arg.to_string())?;
+                }
+                "size" => {
+                    options.sequence_size =
+                        Some(repeat_sequence::parse_size(value).ok_or(arg.to_string())?);
+                }
+                _ => return Err(arg.to_string()),
+            }
+        }
+        Ok(options)
+    }
+
+    /// checks if the game can be played with the options, the error tells the player why not
+    pub fn check_game(&self, game: StartItemType) -> Result<(), String> {
+        if !game.is_playable() {
+            return Err(format!("{} cant be played yet", game.name()));
+        }
+        let unused = match game.is_minesweeper() {
+            true => GameOptions {
+                lives: None,
+                timed: false,
+                flag_win: false,
+                ..*self
+            },
+            false => GameOptions {
+                lives: self.lives,
+                timed: self.timed,
+                flag_win: self.flag_win,
+                ..Default::default()
+            },
+        };
+        if unused != GameOptions::default() {
+            return Err(format!("{unused} doesnt apply to {}", game.name()));
+        }
+        if self.sequence_layout == SequenceLayout::Cube
+            && self
+                .sequence_size
+                .is_some_and(|(width, height)| width != height)
+        {
+            return Err("A cube has square faces, its size has to be like 5x5".to_string());
+        }
+        Ok(())
+    }
+
+    /// the standard leaderboards only count games with standard rules.
+    /// The size of a repeat sequence grid isnt a rule, every size has its own leaderboard
+    pub fn is_standard(&self) -> bool {
+        GameOptions {
+            sequence_size: None,
+            ..*self
+        } == GameOptions::default()
+    }
+}
+
+/// the options in the format [GameOptions::parse] reads
+impl Display for GameOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut options = Vec::new();
+        if let Some(lives) = self.lives {
+            options.push(format!("lives={lives}"));
+        }
+        if self.timed {
+            options.push("timed".to_string());
+        }
+        if self.flag_win {
+            options.push("flagwin".to_string());
+        }
+        if self.speed != SequenceSpeed::Classic {
+            options.push(format!("speed={}", self.speed.name()));
+        }
+        if self.sequence_mode != SequenceMode::Standard {
+            options.push(format!("mode={}", self.sequence_mode.name()));
+        }
+        if self.sequence_display != SequenceDisplay::Plain {
+            options.push(format!("display={}", self.sequence_display.name()));
+        }
+        if self.sequence_variant != SequenceVariant::Normal {
+            options.push(format!("variant={}", self.sequence_variant.name()));
+        }
+        if self.sequence_layout != SequenceLayout::Wall {
+            options.push(format!("layout={}", self.sequence_layout.name()));
+        }
+        if let Some((width, height)) = self.sequence_size {
+            options.push(format!("size={width}x{height}"));
+        }
+        write!(f, "{}", options.join(" "))
+    }
+}
@@ -1,6 +1,8 @@
 use std::{sync::Mutex, time::SystemTime};
 use valence::prelude::*;
 
+use crate::seed::GameSeed;
+
 use postgres::{Client, NoTls};
 
 const CONNECT_STR: &str = "host=localhost user=postgres";
@@ -23,7 +25,8 @@ impl PostgresWrapper {
 
         let mut db_conn = self.0.lock().unwrap();
         match db_conn.query_one(
-            "SELECT MAX(streak) FROM rsg_games WHERE player_uuid = $1",
+            "SELECT MAX(streak) FROM rsg_games WHERE player_uuid = $1 AND speed = 'classic' AND mode = 'standard' AND display = 'plain'
+            AND variant = 'normal' AND layout = 'wall' AND team_size = 1",
             &[&uuid.as_bytes().as_ref()],
         ) {
             Ok(row) => return row.get(0),
@@ -34,14 +37,16 @@ impl PostgresWrapper {
         }
     }
 
+    /// the fastest solo game of a player, without hints, extra lives, the flag rule or a time limit
     pub fn get_minesweeper_fastest(&self, uuid: &UniqueId) -> Option<(i32, i32, i32)> {
         self.check_connection();
 
         let mut db_conn = self.0.lock().unwrap();
         match db_conn.query_one(
-            "SELECT size, dim, MIN(comp_time)
-            FROM minesweeper_games WHERE (player_uuid = $1)
-            GROUP BY size, dim",
+            "SELECT size, dim, comp_time_ms
+            FROM minesweeper_games WHERE player_uuid = $1 AND comp_time_ms IS NOT NULL AND team_size = 1 AND hints = 0 AND lives = 1
+            AND NOT flag_win AND time_limit_ms IS NULL
+            ORDER BY comp_time_ms LIMIT 1",
             &[&uuid.as_bytes().as_ref()],
         ) {
             Ok(row) => return Some((row.get(0), row.get(1), row.get(2))),
@@ -52,13 +57,35 @@ impl PostgresWrapper {
         }
     }
 
-    pub fn insert_rsg(&self, dim: i32, streak: i32, uuid: UniqueId) {
+    pub fn insert_rsg(&self, entry: RsgEntry) {
         self.check_connection();
 
         let time = SystemTime::now();
         match self.0.lock().unwrap().execute(
-            "INSERT INTO rsg_games (date, size, streak, player_uuid) VALUES ($1, $2, $3, $4)",
-            &[&time, &dim, &streak, &uuid.as_bytes().as_ref()],
+            "INSERT INTO rsg_games (date, width, height, streak, speed, mode, display, variant, layout, missed_clicks, total_time_ms, score, correct_inputs, average_reaction_ms, best_reaction_ms, reaction_times_ms, seed, daily, team_size, player_uuid)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)",
+            &[
+                &time,
+                &entry.width,
+                &entry.height,
+                &entry.streak,
+                &entry.speed,
+                &entry.mode,
+                &entry.display,
+                &entry.variant,
+                &entry.layout,
+                &entry.missed_clicks,
+                &entry.total_time_ms,
+                &entry.score,
+                &entry.correct_inputs,
+                &entry.average_reaction_ms,
+                &entry.best_reaction_ms,
+                &entry.reaction_times_ms,
+                &(entry.seed.value as i64),
+                &entry.seed.daily,
+                &entry.team_size,
+                &entry.uuid.as_bytes().as_ref(),
+            ],
         ) {
             Ok(i) => {
                 if i != 1 {
@@ -71,26 +98,272 @@ impl PostgresWrapper {
         }
     }
 
-    pub fn insert_minesweeper(
+    pub fn insert_minesweeper(&self, entry: MinesweeperEntry) {
+        self.check_connection();
+
+        let time = SystemTime::now();
+        match self.0.lock().unwrap().execute(
+            "INSERT INTO minesweeper_games (date, size, dim, comp_time, comp_time_ms, bomb_amt, three_bv, left_clicks, right_clicks, chord_clicks, efficiency, three_bv_per_sec, seed, daily, team_size, hints, lives, mistakes, time_limit_ms, flag_win, cells_opened, correct_flags, player_uuid)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23)",
+            &[
+                &time,
+                &entry.size,
+                &entry.dimension,
+                &entry.comp_time,
+                &entry.comp_time_ms,
+                &entry.bomb_amt,
+                &entry.three_bv,
+                &entry.left_clicks,
+                &entry.right_clicks,
+                &entry.chord_clicks,
+                &entry.efficiency,
+                &entry.three_bv_per_sec,
+                &(entry.seed.value as i64),
+                &entry.seed.daily,
+                &entry.team_size,
+                &entry.hints,
+                &entry.lives,
+                &entry.mistakes,
+                &entry.time_limit_ms,
+                &entry.flag_win,
+                &entry.cells_opened,
+                &entry.correct_flags,
+                &entry.uuid.as_bytes().as_ref(),
+            ],
+        ) {
+            Ok(i) => {
+                if i != 1 {
+                    tracing::error!("Wrong number of database Entries modified.")
+                } else {
+                    tracing::debug!("new database entry saved. (minesweeper)");
+                }
+            }
+            Err(err) => tracing::error!("Couldnt save data into Database {}", err),
+        }
+    }
+
+    /// the best games of a dimension, ranked by 3BV/s so fast times on easy boards dont win.
+    /// Timed games are a category of their own
+    pub fn get_minesweeper_leaderboard(
+        &self,
+        dimension: i32,
+        timed: bool,
+        limit: i64,
+    ) -> Vec<LeaderboardEntry> {
+        self.check_connection();
+
+        let mut db_conn = self.0.lock().unwrap();
+        match db_conn.query(
+            "SELECT player_uuid, size, comp_time_ms, three_bv, three_bv_per_sec, efficiency
+            FROM minesweeper_games
+            WHERE dim = $1 AND three_bv IS NOT NULL AND comp_time_ms IS NOT NULL AND team_size = 1 AND hints = 0 AND lives = 1 AND NOT flag_win
+            AND (time_limit_ms IS NOT NULL) = $2
+            ORDER BY three_bv_per_sec DESC LIMIT $3",
+            &[&dimension, &timed, &limit],
+        ) {
+            Ok(rows) => rows
+                .iter()
+                .map(|row| LeaderboardEntry {
+                    player_uuid: row.get(0),
+                    size: row.get(1),
+                    comp_time_ms: row.get(2),
+                    three_bv: row.get(3),
+                    three_bv_per_sec: row.get(4),
+                    efficiency: row.get(5),
+                })
+                .collect(),
+            Err(err) => {
+                tracing::warn!("The minesweeper leaderboard couldnt be loaded. {}", err);
+                return Vec::new();
+            }
+        }
+    }
+
+    /// the highest streak of every player in a category:
+    /// grid size, display speed, mode, display, variant and layout
+    pub fn get_rsg_leaderboard(
+        &self,
+        (width, height): (i32, i32),
+        category: [&str; 5],
+        limit: i64,
+    ) -> Vec<(Vec<u8>, i32)> {
+        self.check_connection();
+
+        let mut db_conn = self.0.lock().unwrap();
+        match db_conn.query(
+            "SELECT player_uuid, MAX(streak) AS best FROM rsg_games
+            WHERE width = $1 AND height = $2 AND speed = $3 AND mode = $4 AND display = $5
+            AND variant = $6 AND layout = $7 AND team_size = 1
+            GROUP BY player_uuid ORDER BY best DESC LIMIT $8",
+            &[
+                &width,
+                &height,
+                &category[0],
+                &category[1],
+                &category[2],
+                &category[3],
+                &category[4],
+                &limit,
+            ],
+        ) {
+            Ok(rows) => rows.iter().map(|row| (row.get(0), row.get(1))).collect(),
+            Err(err) => {
+                tracing::warn!("The repeat sequence leaderboard couldnt be loaded. {}", err);
+                return Vec::new();
+            }
+        }
+    }
+
+    /// the most correct steps of every player in time attack games, the category is the speed,
+    /// display, variant and layout
+    pub fn get_rsg_time_attack_leaderboard(
+        &self,
+        (width, height): (i32, i32),
+        category: [&str; 4],
+        limit: i64,
+    ) -> Vec<(Vec<u8>, i32)> {
+        self.check_connection();
+
+        let mut db_conn = self.0.lock().unwrap();
+        match db_conn.query(
+            "SELECT player_uuid, MAX(correct_inputs) AS best FROM rsg_games
+            WHERE mode = 'timeattack' AND width = $1 AND height = $2 AND speed = $3 AND display = $4
+            AND variant = $5 AND layout = $6 AND team_size = 1
+            GROUP BY player_uuid ORDER BY best DESC LIMIT $7",
+            &[
+                &width,
+                &height,
+                &category[0],
+                &category[1],
+                &category[2],
+                &category[3],
+                &limit,
+            ],
+        ) {
+            Ok(rows) => rows.iter().map(|row| (row.get(0), row.get(1))).collect(),
+            Err(err) => {
+                tracing::warn!("The time attack leaderboard couldnt be loaded. {}", err);
+                return Vec::new();
+            }
+        }
+    }
+
+    /// the best time of every player in todays daily challenge
+    pub fn get_daily_minesweeper_leaderboard(
         &self,
         size: i32,
         dimension: i32,
-        comp_time: i32,
-        bomb_amt: i32,
+        seed: &GameSeed,
+    ) -> Vec<(Vec<u8>, i32)> {
+        self.check_connection();
+
+        let mut db_conn = self.0.lock().unwrap();
+        match db_conn.query(
+            "SELECT player_uuid, MIN(comp_time_ms) AS best FROM minesweeper_games
+            WHERE daily AND seed = $1 AND size = $2 AND dim = $3 AND date >= CURRENT_DATE AND team_size = 1 AND hints = 0 AND lives = 1 AND NOT flag_win
+            AND time_limit_ms IS NULL
+            GROUP BY player_uuid ORDER BY best LIMIT 10",
+            &[&(seed.value as i64), &size, &dimension],
+        ) {
+            Ok(rows) => rows.iter().map(|row| (row.get(0), row.get(1))).collect(),
+            Err(err) => {
+                tracing::warn!("The daily leaderboard couldnt be loaded. {}", err);
+                return Vec::new();
+            }
+        }
+    }
+
+    /// the highest streak of every player in todays daily challenge
+    pub fn get_daily_rsg_leaderboard(
+        &self,
+        (width, height): (i32, i32),
+        seed: &GameSeed,
+    ) -> Vec<(Vec<u8>, i32)> {
+        self.check_connection();
+
+        let mut db_conn = self.0.lock().unwrap();
+        match db_conn.query(
+            "SELECT player_uuid, MAX(streak) AS best FROM rsg_games
+            WHERE daily AND seed = $1 AND width = $2 AND height = $3 AND date >= CURRENT_DATE AND speed = 'classic'
+            AND mode = 'standard' AND display = 'plain' AND variant = 'normal'
+            AND layout = 'wall' AND team_size = 1
+            GROUP BY player_uuid ORDER BY best DESC LIMIT 10",
+            &[&(seed.value as i64), &width, &height],
+        ) {
+            Ok(rows) => rows.iter().map(|row| (row.get(0), row.get(1))).collect(),
+            Err(err) => {
+                tracing::warn!("The daily leaderboard couldnt be loaded. {}", err);
+                return Vec::new();
+            }
+        }
+    }
+
+    /// saves an encoded game recording, returns the id to load it again
+    pub fn insert_replay(
+        &self,
+        game: &str,
+        seed: &GameSeed,
+        data: &[u8],
         uuid: UniqueId,
+    ) -> Option<i32> {
+        self.check_connection();
+
+        let time = SystemTime::now();
+        match self.0.lock().unwrap().query_one(
+            "INSERT INTO replays (date, game, seed, player_uuid, data) VALUES ($1, $2, $3, $4, $5)
+            RETURNING id",
+            &[
+                &time,
+                &game,
+                &(seed.value as i64),
+                &uuid.as_bytes().as_ref(),
+                &data,
+            ],
+        ) {
+            Ok(row) => {
+                tracing::debug!("new database entry saved. (replay)");
+                return Some(row.get(0));
+            }
+            Err(err) => {
+                tracing::error!("Couldnt save data into Database {}", err);
+                return None;
+            }
+        }
+    }
+
+    pub fn get_replay(&self, id: i32) -> Option<Vec<u8>> {
+        self.check_connection();
+
+        let mut db_conn = self.0.lock().unwrap();
+        match db_conn.query_one("SELECT data FROM replays WHERE id = $1", &[&id]) {
+            Ok(row) => return row.get(0),
+            Err(err) => {
+                tracing::warn!("A replay couldnt be loaded. {}", err);
+                return None;
+            }
+        }
+    }
+
+    pub fn insert_race(
+        &self,
+        seed: &GameSeed,
+        size: i32,
+        player_count: i32,
+        winner: UniqueId,
+        comp_time_ms: i32,
     ) {
         self.check_connection();
 
         let time = SystemTime::now();
         match self.0.lock().unwrap().execute(
-            "INSERT INTO minesweeper_games (date, size, dim, comp_time, bomb_amt, player_uuid) VALUES ($1, $2, $3, $4, $5, $6)",
-            &[&time, &size, &dimension, &comp_time, &bomb_amt, &uuid.as_bytes().as_ref()],
+            "INSERT INTO minesweeper_races (date, seed, size, player_count, winner_uuid, comp_time_ms) VALUES ($1, $2, $3, $4, $5, $6)",
+            &[&time, &(seed.value as i64), &size, &player_count, &winner.as_bytes().as_ref(), &comp_time_ms],
         ) {
             Ok(i) => {
                 if i != 1 {
                     tracing::error!("Wrong number of database Entries modified.")
                 } else {
-                    tracing::debug!("new database entry saved. (minesweeper)");
+                    tracing::debug!("new database entry saved. (race)");
                 }
             }
             Err(err) => tracing::error!("Couldnt save data into Database {}", err),
@@ -105,3 +378,68 @@ impl PostgresWrapper {
         }
     }
 }
+
+pub struct MinesweeperEntry {
+    pub size: i32,
+    pub dimension: i32,
+    /// in ticks
+    pub comp_time: i32,
+    pub comp_time_ms: i32,
+    pub bomb_amt: i32,
+    pub three_bv: i32,
+    pub left_clicks: i32,
+    pub right_clicks: i32,
+    pub chord_clicks: i32,
+    pub efficiency: f32,
+    pub three_bv_per_sec: f32,
+    pub seed: GameSeed,
+    /// the amount of players who cleared the board together
+    pub team_size: i32,
+    /// games with hints are kept out of the leaderboards
+    pub hints: i32,
+    /// casual games with more than one life are kept out of the leaderboards as well
+    pub lives: i32,
+    pub mistakes: i32,
+    /// timed games have their own leaderboard
+    pub time_limit_ms: Option<i32>,
+    /// games won with the flag rule are kept out of the leaderboards
+    pub flag_win: bool,
+    /// what this player did in the game
+    pub cells_opened: i32,
+    pub correct_flags: i32,
+    pub uuid: UniqueId,
+}
+
+pub struct RsgEntry {
+    pub width: i32,
+    pub height: i32,
+    pub streak: i32,
+    /// the names of the display speed, mode, display, variant and layout,
+    /// streaks are only ranked against the same ones
+    pub speed: &'static str,
+    pub mode: &'static str,
+    pub display: &'static str,
+    pub variant: String,
+    pub layout: &'static str,
+    pub missed_clicks: i32,
+    pub total_time_ms: i32,
+    pub score: i32,
+    pub correct_inputs: i32,
+    pub average_reaction_ms: Option<i32>,
+    pub best_reaction_ms: Option<i32>,
+    /// the time every input took since the sequence was shown or since the previous input
+    pub reaction_times_ms: Vec<i32>,
+    pub seed: GameSeed,
+    /// the amount of players who entered inputs, team games are kept out of the leaderboards
+    pub team_size: i32,
+    pub uuid: UniqueId,
+}
+
+pub struct LeaderboardEntry {
+    pub player_uuid: Vec<u8>,
+    pub size: i32,
+    pub comp_time_ms: i32,
+    pub three_bv: i32,
+    pub three_bv_per_sec: f32,
+    pub efficiency: f32,
+}
@@ -1,14 +1,306 @@
-use rand::Rng;
+use rand::{rngs::StdRng, Rng};
 use valence::{
     prelude::*,
     protocol::{sound::SoundCategory, Sound},
 };
 
 use crate::{
-    custom_game::{BuildGameError, CustomGame},
-    postgres_wrapper::PostgresWrapper,
+    custom_game::{BuildGameError, CustomGame, Participants},
+    options::GameOptions,
+    postgres_wrapper::{PostgresWrapper, RsgEntry},
+    seed::GameSeed,
 };
 
+/// points for every correct input
+const POINTS_PER_INPUT: u32 = 10;
+/// inputs within this many ticks of the previous one get a bonus point for every tick left
+const FAST_INPUT_TICKS: usize = 20;
+/// points lost for every missed click
+const MISS_PENALTY: u32 = 25;
+/// how long the wall behind a pressed button lights up
+const FLASH_TICKS: usize = 6;
+const CORRECT_FLASH: BlockState = BlockState::LIME_CONCRETE;
+const WRONG_FLASH: BlockState = BlockState::REDSTONE_BLOCK;
+/// on the floor a tile only counts once the player stood on it for this long,
+/// so walking over other tiles on the way isnt an input
+const STEP_DWELL_TICKS: usize = 8;
+/// how long a time attack game lasts
+const TIME_ATTACK_TICKS: usize = 20 * 60;
+/// games with at least this many inputs are checked for inhumanly consistent reaction times
+const CONSISTENCY_MIN_INPUTS: usize = 20;
+/// the standard deviation of the reaction times in ticks below which a game is logged
+const CONSISTENCY_MIN_DEVIATION: f32 = 0.5;
+
+/// the width and height of a grid if no size is given
+pub const DEFAULT_SIZE: (usize, usize) = (5, 5);
+
+/// parses a grid size like `9x4`, the width and height can be 2 to 9
+pub fn parse_size(text: &str) -> Option<(usize, usize)> {
+    let (width, height) = text.split_once('x')?;
+    let size: (usize, usize) = (width.parse().ok()?, height.parse().ok()?);
+    return [size.0, size.1]
+        .iter()
+        .all(|d| (2..=9).contains(d))
+        .then_some(size);
+}
+
+/// If the sequence is shown on a wall of buttons, on floor tiles the player walks onto
+/// or on the inside of a cube of buttons around the player
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum SequenceLayout {
+    #[default]
+    Wall,
+    Floor,
+    Cube,
+}
+
+impl SequenceLayout {
+    /// the name used in chat commands and the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            SequenceLayout::Wall => "wall",
+            SequenceLayout::Floor => "floor",
+            SequenceLayout::Cube => "cube",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SequenceLayout> {
+        [
+            SequenceLayout::Wall,
+            SequenceLayout::Floor,
+            SequenceLayout::Cube,
+        ]
+        .into_iter()
+        .find(|l| l.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// How many clicks can be missed and how long the player has for the next input
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum SequenceMode {
+    /// three missed clicks and ten seconds for every input
+    #[default]
+    Standard,
+    /// the first missed click or five seconds without input end the game
+    SuddenDeath,
+    /// five missed clicks and thirty seconds for every input
+    Relaxed,
+    /// as many correct steps as possible within a minute, missed clicks only cost points
+    TimeAttack,
+}
+
+impl SequenceMode {
+    pub fn all() -> [SequenceMode; 4] {
+        [
+            SequenceMode::Standard,
+            SequenceMode::SuddenDeath,
+            SequenceMode::Relaxed,
+            SequenceMode::TimeAttack,
+        ]
+    }
+
+    /// the name used in chat commands and the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            SequenceMode::Standard => "standard",
+            SequenceMode::SuddenDeath => "suddendeath",
+            SequenceMode::Relaxed => "relaxed",
+            SequenceMode::TimeAttack => "timeattack",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SequenceMode> {
+        SequenceMode::all()
+            .into_iter()
+            .find(|m| m.name().eq_ignore_ascii_case(name))
+    }
+
+    /// the amount of missed clicks which ends the game, None if only the time limit ends it
+    pub fn lives(&self) -> Option<usize> {
+        match self {
+            SequenceMode::Standard => Some(3),
+            SequenceMode::SuddenDeath => Some(1),
+            SequenceMode::Relaxed => Some(5),
+            SequenceMode::TimeAttack => None,
+        }
+    }
+
+    /// the ticks after which the whole game ends
+    pub fn time_limit(&self) -> Option<usize> {
+        match self {
+            SequenceMode::TimeAttack => Some(TIME_ATTACK_TICKS),
+            _ => None,
+        }
+    }
+
+    /// the ticks without an input after which the game ends
+    pub fn input_timeout(&self) -> usize {
+        match self {
+            SequenceMode::Standard => 20 * 10,
+            SequenceMode::SuddenDeath => 20 * 5,
+            SequenceMode::Relaxed => 20 * 30,
+            SequenceMode::TimeAttack => TIME_ATTACK_TICKS,
+        }
+    }
+}
+
+/// the note block sound of every row of the wall in musical games, from the bottom up.
+/// There is one for every row a grid can have, so every cell of a column sounds different
+const ROW_INSTRUMENTS: [Sound; 9] = [
+    Sound::BlockNoteBlockHarp,
+    Sound::BlockNoteBlockBell,
+    Sound::BlockNoteBlockFlute,
+    Sound::BlockNoteBlockGuitar,
+    Sound::BlockNoteBlockChime,
+    Sound::BlockNoteBlockXylophone,
+    Sound::BlockNoteBlockBanjo,
+    Sound::BlockNoteBlockIronXylophone,
+    Sound::BlockNoteBlockPling,
+];
+/// the notes of a pentatonic scale, in note block clicks, so any sequence sounds fine
+const PENTATONIC: [usize; 5] = [0, 2, 4, 7, 9];
+const CELL_COLORS: [BlockState; 16] = [
+    BlockState::RED_CONCRETE,
+    BlockState::ORANGE_CONCRETE,
+    BlockState::YELLOW_CONCRETE,
+    BlockState::LIME_CONCRETE,
+    BlockState::GREEN_CONCRETE,
+    BlockState::CYAN_CONCRETE,
+    BlockState::LIGHT_BLUE_CONCRETE,
+    BlockState::BLUE_CONCRETE,
+    BlockState::PURPLE_CONCRETE,
+    BlockState::MAGENTA_CONCRETE,
+    BlockState::PINK_CONCRETE,
+    BlockState::BROWN_CONCRETE,
+    BlockState::WHITE_CONCRETE,
+    BlockState::LIGHT_GRAY_CONCRETE,
+    BlockState::GRAY_CONCRETE,
+    BlockState::BLACK_CONCRETE,
+];
+
+/// How the steps of the sequence are shown
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum SequenceDisplay {
+    /// the wall lights up red
+    #[default]
+    Plain,
+    /// every cell lights up in its own colour and plays its own note
+    Music,
+    /// only the notes are played, the sequence has to be followed by ear
+    Audio,
+}
+
+impl SequenceDisplay {
+    pub fn all() -> [SequenceDisplay; 3] {
+        [
+            SequenceDisplay::Plain,
+            SequenceDisplay::Music,
+            SequenceDisplay::Audio,
+        ]
+    }
+
+    /// the name used in chat commands and the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            SequenceDisplay::Plain => "plain",
+            SequenceDisplay::Music => "music",
+            SequenceDisplay::Audio => "audio",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SequenceDisplay> {
+        SequenceDisplay::all()
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// In which order the sequence has to be entered
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum SequenceVariant {
+    /// in the order it is shown
+    #[default]
+    Normal,
+    /// from the newest step back to the first one
+    Reverse,
+    /// in the order it is shown, but mirrored from left to right
+    Mirrored,
+    /// only the newest steps, at most this many
+    Newest(usize),
+}
+
+impl SequenceVariant {
+    /// the name used in chat commands and the database, `newest3` for the newest three steps
+    pub fn name(&self) -> String {
+        match self {
+            SequenceVariant::Normal => "normal".to_string(),
+            SequenceVariant::Reverse => "reverse".to_string(),
+            SequenceVariant::Mirrored => "mirror".to_string(),
+            SequenceVariant::Newest(n) => format!("newest{n}"),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SequenceVariant> {
+        match name.to_ascii_lowercase().as_str() {
+            "normal" => Some(SequenceVariant::Normal),
+            "reverse" => Some(SequenceVariant::Reverse),
+            "mirror" => Some(SequenceVariant::Mirrored),
+            name => name
+                .strip_prefix("newest")?
+                .parse()
+                .ok()
+                .filter(|n| (1..=9).contains(n))
+                .map(SequenceVariant::Newest),
+        }
+    }
+}
+
+/// How fast the sequence is shown, streaks of different speeds are ranked separately
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum SequenceSpeed {
+    /// one step every second, no matter how long the sequence is
+    #[default]
+    Classic,
+    /// starts like classic and gets faster with every step of the sequence
+    Progressive,
+    /// starts twice as fast as classic and gets faster as well
+    Fast,
+}
+
+impl SequenceSpeed {
+    pub fn all() -> [SequenceSpeed; 3] {
+        [
+            SequenceSpeed::Classic,
+            SequenceSpeed::Progressive,
+            SequenceSpeed::Fast,
+        ]
+    }
+
+    /// the name used in chat commands and the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            SequenceSpeed::Classic => "classic",
+            SequenceSpeed::Progressive => "progressive",
+            SequenceSpeed::Fast => "fast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SequenceSpeed> {
+        SequenceSpeed::all()
+            .into_iter()
+            .find(|s| s.name().eq_ignore_ascii_case(name))
+    }
+
+    /// the ticks each step is shown for, and waited before a new step, at a sequence length
+    pub fn step_ticks(&self, length: usize) -> usize {
+        match self {
+            SequenceSpeed::Classic => 20,
+            SequenceSpeed::Progressive => 20usize.saturating_sub(length / 2).max(8),
+            SequenceSpeed::Fast => 10usize.saturating_sub(length / 4).max(4),
+        }
+    }
+}
+
 #[derive(PartialEq)]
 pub enum GameState {
     Idle,
@@ -16,23 +308,61 @@ pub enum GameState {
     WaitForInput,
 }
 
-pub struct RepeatSequenceGame<const DIM: usize> {
+pub struct RepeatSequenceGame {
     dir: Direction,
-    wall_blocks: [[BlockPos; DIM]; DIM],
-    button_blocks: [[BlockPos; DIM]; DIM],
+    /// the block the player stood in when starting the game, the cube is built around it
+    start: BlockPos,
+    width: usize,
+    height: usize,
+    /// indexed by the column from the left and the row from the bottom, or from the front on the floor.
+    /// The faces of the cube are next to each other, so it has six times as many columns
+    wall_blocks: Vec<Vec<BlockPos>>,
+    button_blocks: Vec<Vec<BlockPos>>,
     is_built: bool,
     sequence: Vec<(BlockPos, BlockPos)>,
-    player: (Entity, UniqueId),
+    participants: Participants,
+    seed: GameSeed,
+    rng: StdRng,
     state: GameState,
     ticks: usize,
     input_progres: usize,
     should_despawn: bool,
     missed_clicks: usize,
+    speed: SequenceSpeed,
+    mode: SequenceMode,
+    display: SequenceDisplay,
+    variant: SequenceVariant,
+    /// rewards fast and accurate inputs
+    score: u32,
+    /// every correctly entered step, what time attack games are ranked by
+    correct_inputs: usize,
+    /// the tick the current input could start, after the sequence was shown or the previous input
+    input_ready: usize,
+    /// the ticks every input took since it could start, correct or not
+    reaction_ticks: Vec<usize>,
+    total_ticks: usize,
+    /// wall blocks to change once total_ticks reaches the tick, for the click feedback
+    scheduled_blocks: Vec<(usize, BlockPos, BlockState)>,
+    /// on the floor the wall blocks are the tiles and also the inputs, there are no buttons
+    layout: SequenceLayout,
+    /// the ground the floor tiles were built on, it is put back on reset
+    replaced_floor: Vec<(BlockPos, BlockState)>,
+    /// the block the player is standing on and since when
+    standing_on: Option<(BlockPos, usize)>,
+    /// the owner and everyone who entered inputs, team games are kept out of the leaderboards
+    team: Vec<Entity>,
 }
 
-impl<const DIM: usize> RepeatSequenceGame<DIM> {
-    ///the position of the player starting the game, their yaw, and their entity
-    pub fn new(pos: &Position, yaw: f32, player: (Entity, UniqueId)) -> RepeatSequenceGame<DIM> {
+impl RepeatSequenceGame {
+    ///the position of the player starting the game, their yaw, their entity, the seed of the sequence
+    /// and the width and height of the grid
+    pub fn new(
+        pos: &Position,
+        yaw: f32,
+        player: (Entity, UniqueId),
+        seed: GameSeed,
+        size: (usize, usize),
+    ) -> RepeatSequenceGame {
         let normalized_angle = yaw - (360.0 * yaw.div_euclid(360.0));
         let dir_num = (normalized_angle / 90.0).round();
         let dir = match dir_num as isize {
@@ -45,20 +375,37 @@ impl<const DIM: usize> RepeatSequenceGame<DIM> {
         };
         // dir is now the direction the player is looking
 
-        let bottom_left = Self::player_pos_to_bottom_left(pos, &dir);
-        return RepeatSequenceGame::<DIM>::new_with_bottom_left(bottom_left, dir, player);
+        let bottom_left = Self::player_pos_to_bottom_left(pos, &dir, size.0);
+        let mut game =
+            RepeatSequenceGame::new_with_bottom_left(bottom_left, dir, player, seed, size);
+        game.start = BlockPos::new(
+            pos.x.floor() as i32,
+            pos.y.floor() as i32,
+            pos.z.floor() as i32,
+        );
+        return game;
     }
 
-    /// takes the bottom left block of the game, the direction of the game and the player
+    /// takes the bottom left block of the game, the direction of the game, the player, the seed
+    /// and the width and height of the grid
     pub fn new_with_bottom_left(
         bottom_left: BlockPos,
         dir: Direction,
         player: (Entity, UniqueId),
-    ) -> RepeatSequenceGame<DIM> {
-        let (wall_blocks, button_blocks) = Self::get_block_positions(&dir, &bottom_left);
+        seed: GameSeed,
+        (width, height): (usize, usize),
+    ) -> RepeatSequenceGame {
+        let (wall_blocks, button_blocks) =
+            Self::get_block_positions(&dir, &bottom_left, width, height);
         RepeatSequenceGame {
             dir,
-            player,
+            start: bottom_left,
+            width,
+            height,
+            participants: Participants::new(player),
+            team: vec![player.0],
+            seed,
+            rng: seed.rng(),
             sequence: Vec::new(),
             is_built: false,
             wall_blocks,
@@ -68,11 +415,50 @@ impl<const DIM: usize> RepeatSequenceGame<DIM> {
             input_progres: 0,
             should_despawn: false,
             missed_clicks: 0,
+            speed: SequenceSpeed::Classic,
+            mode: SequenceMode::Standard,
+            display: SequenceDisplay::Plain,
+            variant: SequenceVariant::Normal,
+            score: 0,
+            correct_inputs: 0,
+            input_ready: 0,
+            reaction_ticks: Vec::new(),
+            total_ticks: 0,
+            scheduled_blocks: Vec::new(),
+            layout: SequenceLayout::Wall,
+            replaced_floor: Vec::new(),
+            standing_on: None,
         }
     }
 
+    /// applies the display speed, mode, display, variant and layout of the options
+    pub fn with_options(mut self, options: &GameOptions) -> Self {
+        self.layout = options.sequence_layout;
+        if self.layout == SequenceLayout::Floor {
+            let floor = Self::get_floor_positions(
+                &self.dir,
+                &self.wall_blocks[0][0],
+                self.width,
+                self.height,
+            );
+            self.wall_blocks = floor.clone();
+            self.button_blocks = floor;
+        }
+        if self.layout == SequenceLayout::Cube {
+            // every face is a square as wide as the grid
+            self.height = self.width;
+            (self.wall_blocks, self.button_blocks) =
+                Self::get_cube_positions(&self.start, self.width);
+        }
+        self.speed = options.speed;
+        self.mode = options.sequence_mode;
+        self.display = options.sequence_display;
+        self.variant = options.sequence_variant;
+        self
+    }
+
     /// turns the players position into the position of the bottom left block of the game
-    fn player_pos_to_bottom_left(player_pos: &Position, dir: &Direction) -> BlockPos {
+    fn player_pos_to_bottom_left(player_pos: &Position, dir: &Direction, width: usize) -> BlockPos {
         let mut pos_block = BlockPos::new(
             player_pos.x as i32,
             player_pos.y as i32,
@@ -83,7 +469,7 @@ impl<const DIM: usize> RepeatSequenceGame<DIM> {
         pos_block = pos_block.get_in_direction(*dir);
         pos_block = pos_block.get_in_direction(*dir);
 
-        let offset: i32 = -(DIM as i32 / 2) - 1;
+        let offset: i32 = -(width as i32 / 2) - 1;
         match dir {
             Direction::North => pos_block.offset(offset, 0, 0),
             Direction::East => pos_block.offset(0, 0, offset),
@@ -97,13 +483,15 @@ impl<const DIM: usize> RepeatSequenceGame<DIM> {
     fn get_block_positions(
         dir: &Direction,
         bottom_left: &BlockPos,
-    ) -> ([[BlockPos; DIM]; DIM], [[BlockPos; DIM]; DIM]) {
+        width: usize,
+        height: usize,
+    ) -> (Vec<Vec<BlockPos>>, Vec<Vec<BlockPos>>) {
         let opp_dir = opposite_dir(dir);
-        let mut wall = [[BlockPos::new(0, 0, 0); DIM]; DIM];
-        let mut buttons = [[BlockPos::new(0, 0, 0); DIM]; DIM];
+        let mut wall = vec![vec![BlockPos::new(0, 0, 0); height]; width];
+        let mut buttons = vec![vec![BlockPos::new(0, 0, 0); height]; width];
 
-        for y in 0..DIM {
-            for xorz in 0..DIM {
+        for y in 0..height {
+            for xorz in 0..width {
                 if *dir == Direction::North || *dir == Direction::South {
                     wall[xorz][y] = bottom_left.offset(xorz as i32, y as i32, 0);
                     buttons[xorz][y] = bottom_left
@@ -120,19 +508,187 @@ impl<const DIM: usize> RepeatSequenceGame<DIM> {
         return (wall, buttons);
     }
 
+    /// A hollow cube around the start with a grid on the inside of every face.
+    /// The faces are next to each other from the left to the right: the four sides as seen when
+    /// turning to the right, then the floor and the ceiling. The room inside is one block larger
+    /// than the grids in every direction, so buttons of different faces never share a block
+    fn get_cube_positions(
+        start: &BlockPos,
+        edge: usize,
+    ) -> (Vec<Vec<BlockPos>>, Vec<Vec<BlockPos>>) {
+        let n = edge as i32;
+        let min = start.offset(-(n + 2) / 2, 0, -(n + 2) / 2);
+        let mut wall = Vec::new();
+        let mut buttons = Vec::new();
+        // the direction the buttons of each face point to
+        for facing in [
+            Direction::South,
+            Direction::West,
+            Direction::North,
+            Direction::East,
+            Direction::Up,
+            Direction::Down,
+        ] {
+            for a in 1..=n {
+                let column: Vec<BlockPos> = (1..=n)
+                    .map(|b| match facing {
+                        Direction::South => min.offset(a, b, -1),
+                        Direction::West => min.offset(n + 2, b, a),
+                        Direction::North => min.offset(n + 1 - a, b, n + 2),
+                        Direction::East => min.offset(-1, b, n + 1 - a),
+                        Direction::Up => min.offset(a, -1, b),
+                        Direction::Down => min.offset(a, n + 2, b),
+                    })
+                    .collect();
+                buttons.push(column.iter().map(|p| p.get_in_direction(facing)).collect());
+                wall.push(column);
+            }
+        }
+        return (wall, buttons);
+    }
+
+    /// the amount of columns, on the cube the columns of all faces
+    fn columns(&self) -> usize {
+        self.wall_blocks.len()
+    }
+
+    fn rows(&self) -> usize {
+        self.wall_blocks[0].len()
+    }
+
+    /// the cell a wall or button block belongs to
+    fn cell_coords(&self, pos: &BlockPos) -> Option<(usize, usize)> {
+        (0..self.columns())
+            .flat_map(|x| (0..self.rows()).map(move |y| (x, y)))
+            .find(|(x, y)| self.wall_blocks[*x][*y] == *pos || self.button_blocks[*x][*y] == *pos)
+    }
+
+    /// the colour and note of the cell a wall or button block belongs to, in musical games
+    fn cell_tone(&self, pos: &BlockPos) -> Option<(BlockState, Sound, f32)> {
+        let (x, y) = self.cell_coords(pos)?;
+        let color = CELL_COLORS[(y * self.columns() + x) % CELL_COLORS.len()];
+        // the faces of the cube repeat the notes of the wall
+        let column = x % self.width;
+        let note = PENTATONIC[column % PENTATONIC.len()] + 12 * (column / PENTATONIC.len());
+        // note blocks cover two octaves, click 12 is the normal pitch
+        let pitch = 2f32.powf((note as f32 - 12.0) / 12.0);
+        return Some((color, ROW_INSTRUMENTS[y % ROW_INSTRUMENTS.len()], pitch));
+    }
+
+    /// the amount of inputs needed to complete a round
+    fn input_length(&self) -> usize {
+        match self.variant {
+            SequenceVariant::Newest(n) => n.min(self.sequence.len()),
+            _ => self.sequence.len(),
+        }
+    }
+
+    /// the button which has to be pressed next, depending on the variant
+    fn expected_input(&self) -> Option<BlockPos> {
+        let len = self.sequence.len();
+        let step = match self.variant {
+            SequenceVariant::Reverse => len.checked_sub(self.input_progres + 1)?,
+            SequenceVariant::Newest(_) => len - self.input_length() + self.input_progres,
+            _ => self.input_progres,
+        };
+        let button = self.sequence.get(step)?.0;
+        if self.variant != SequenceVariant::Mirrored {
+            return Some(button);
+        }
+        let (x, y) = self.cell_coords(&button)?;
+        // every face of the cube is mirrored on its own
+        let column = x % self.width;
+        return Some(self.button_blocks[x - column + self.width - 1 - column][y]);
+    }
+
+    /// lights up a step of the sequence and plays its note, depending on the display
+    fn show_step(&self, step: &(BlockPos, BlockPos), layer: &mut ChunkLayer) {
+        let tone = self.cell_tone(&step.1);
+        match (self.display, tone) {
+            (SequenceDisplay::Music, Some((color, instrument, pitch))) => {
+                layer.set_block(step.1, color);
+                play_note(layer, instrument, &step.1, pitch);
+            }
+            (SequenceDisplay::Audio, Some((_, instrument, pitch))) => {
+                play_note(layer, instrument, &step.1, pitch);
+            }
+            _ => {
+                layer.set_block(step.1, BlockState::RED_CONCRETE);
+            }
+        }
+    }
+
+    /// lights up the wall behind a button after the delay, and turns it back to stone after a moment
+    fn flash(
+        &mut self,
+        button: &BlockPos,
+        block: BlockState,
+        delay: usize,
+        layer: &mut ChunkLayer,
+    ) {
+        let Some((x, y)) = self.cell_coords(button) else {
+            return;
+        };
+        let wall = self.wall_blocks[x][y];
+        // a new flash replaces the one still running on the same block
+        self.scheduled_blocks.retain(|(_, pos, _)| *pos != wall);
+        if delay == 0 {
+            layer.set_block(wall, block);
+        } else {
+            self.scheduled_blocks
+                .push((self.total_ticks + delay, wall, block));
+        }
+        self.scheduled_blocks.push((
+            self.total_ticks + delay + FLASH_TICKS,
+            wall,
+            BlockState::STONE,
+        ));
+    }
+
+    /// sets the scheduled blocks which are due, or all of them
+    fn apply_scheduled_blocks(&mut self, all: bool, layer: &mut ChunkLayer) {
+        let now = self.total_ticks;
+        self.scheduled_blocks.retain(|(tick, pos, block)| {
+            if !all && *tick > now {
+                return true;
+            }
+            layer.set_block(*pos, *block);
+            return false;
+        });
+    }
+
+    /// the floor tiles start below the bottom left block of the wall and lead away from the player
+    fn get_floor_positions(
+        dir: &Direction,
+        bottom_left: &BlockPos,
+        width: usize,
+        height: usize,
+    ) -> Vec<Vec<BlockPos>> {
+        let mut floor = vec![vec![BlockPos::new(0, 0, 0); height]; width];
+        for xorz in 0..width {
+            let mut pos = if *dir == Direction::North || *dir == Direction::South {
+                bottom_left.offset(xorz as i32, -1, 0)
+            } else {
+                bottom_left.offset(0, -1, xorz as i32)
+            };
+            for row in floor[xorz].iter_mut() {
+                *row = pos;
+                pos = pos.get_in_direction(*dir);
+            }
+        }
+        return floor;
+    }
+
     /// generates a new step of the sequence
     fn generate_sequence(&mut self) {
-        let mut rng = rand::thread_rng();
-        let xcoord = rng.gen_range(0..DIM);
-        let ycoord = rng.gen_range(0..DIM);
-
-        if self
-            .sequence
-            .last()
-            .unwrap_or(&(BlockPos::default(), BlockPos::default()))
-            .0
-            == self.button_blocks[xcoord][ycoord]
-        {
+        let xcoord = self.rng.gen_range(0..self.columns());
+        let ycoord = self.rng.gen_range(0..self.rows());
+
+        let last = self.sequence.last().and_then(|s| self.cell_coords(&s.0));
+        // on the cube every step is on another face, so the player has to turn around
+        let same_face = self.layout == SequenceLayout::Cube
+            && last.is_some_and(|(x, _)| x / self.width == xcoord / self.width);
+        if same_face || last == Some((xcoord, ycoord)) {
             self.generate_sequence();
         } else {
             self.sequence.push((
@@ -141,36 +697,322 @@ impl<const DIM: usize> RepeatSequenceGame<DIM> {
             ));
         }
     }
+
+    /// handles a pressed button, or a floor tile the player stood on
+    fn join_team(&mut self, player: Entity) {
+        if !self.team.contains(&player) {
+            self.team.push(player);
+        }
+    }
+
+    /// `made_at` is the tick the input was made, floor tiles only count as an input after the dwell time
+    /// returns false if the click wasnt on a button while the game waited for input
+    fn input(&mut self, click_pos: &BlockPos, made_at: usize, layer: &mut ChunkLayer) -> bool {
+        if self.state == GameState::WaitForInput
+            && !self.should_despawn
+            && self.button_blocks.concat().contains(click_pos)
+        {
+            self.reaction_ticks
+                .push(made_at.saturating_sub(self.input_ready));
+            self.input_ready = self.total_ticks;
+            // the timeout for the next input starts now, whether this one was right or not
+            let ticks = std::mem::take(&mut self.ticks);
+            if self.expected_input() == Some(*click_pos) {
+                match (self.display, self.cell_tone(click_pos)) {
+                    (SequenceDisplay::Plain, _) | (_, None) => {
+                        sound(layer, Sound::BlockNoteBlockBanjo, click_pos)
+                    }
+                    (_, Some((_, instrument, pitch))) => {
+                        play_note(layer, instrument, click_pos, pitch)
+                    }
+                }
+                // audio games show nothing, the note is the feedback
+                if self.display != SequenceDisplay::Audio {
+                    self.flash(click_pos, CORRECT_FLASH, 0, layer);
+                }
+                self.score += POINTS_PER_INPUT + FAST_INPUT_TICKS.saturating_sub(ticks) as u32;
+                self.correct_inputs += 1;
+                self.input_progres += 1;
+                if self.input_progres == self.input_length() {
+                    self.state = GameState::Idle;
+                    self.input_progres = 0;
+                    sound(layer, Sound::BlockBeehiveEnter, click_pos);
+                }
+            } else {
+                sound(layer, Sound::EntityCreeperDeath, click_pos);
+                if self.display == SequenceDisplay::Audio {
+                    // plays the note of the button which would have been right instead of showing it
+                    if let Some(expected) = self.expected_input() {
+                        if let Some((_, instrument, pitch)) = self.cell_tone(&expected) {
+                            play_note(layer, instrument, &expected, pitch);
+                        }
+                    }
+                } else {
+                    self.flash(click_pos, WRONG_FLASH, 0, layer);
+                    // shows which button would have been right
+                    if let Some(expected) = self.expected_input() {
+                        let block = match (self.display, self.cell_tone(&expected)) {
+                            (SequenceDisplay::Music, Some((color, _, _))) => color,
+                            _ => BlockState::RED_CONCRETE,
+                        };
+                        self.flash(&expected, block, FLASH_TICKS, layer);
+                    }
+                }
+                self.missed_clicks += 1;
+                self.score = self.score.saturating_sub(MISS_PENALTY);
+
+                if self
+                    .mode
+                    .lives()
+                    .is_some_and(|lives| self.missed_clicks >= lives)
+                {
+                    self.finish();
+                }
+            }
+            self.show_status();
+            return true;
+        }
+        return false;
+    }
+
+    /// the average and best reaction time in milliseconds
+    fn reaction_times_ms(&self) -> Option<(usize, usize)> {
+        let best = self.reaction_ticks.iter().min()?;
+        let average = self.reaction_ticks.iter().sum::<usize>() * 50 / self.reaction_ticks.len();
+        return Some((average, best * 50));
+    }
+
+    /// ends the game and reports the reaction times
+    fn finish(&mut self) {
+        // the game can be ticked again before it despawns
+        if self.should_despawn {
+            return;
+        }
+        self.should_despawn = true;
+        let Some((average, best)) = self.reaction_times_ms() else {
+            return;
+        };
+        self.participants.announce(format!(
+            "Reaction times: {average}ms on average, {best}ms at best"
+        ));
+
+        let inputs = self.reaction_ticks.len();
+        if inputs < CONSISTENCY_MIN_INPUTS {
+            return;
+        }
+        let mean = self.reaction_ticks.iter().sum::<usize>() as f32 / inputs as f32;
+        let variance = self
+            .reaction_ticks
+            .iter()
+            .map(|t| (*t as f32 - mean).powi(2))
+            .sum::<f32>()
+            / inputs as f32;
+        if variance.sqrt() < CONSISTENCY_MIN_DEVIATION {
+            tracing::warn!(
+                "repeat sequence: {} reaction times of {:?} are inhumanly consistent",
+                inputs,
+                self.participants.owner().1
+            );
+        }
+    }
+
+    /// the score and what is left of the missed clicks or the time
+    fn show_status(&mut self) {
+        let left = match (self.mode.lives(), self.mode.time_limit()) {
+            (Some(lives), _) => format!("{} missed click(s) left", lives - self.missed_clicks),
+            (None, Some(limit)) => format!(
+                "{:.1}s left",
+                limit.saturating_sub(self.total_ticks) as f32 / 20.0
+            ),
+            (None, None) => return,
+        };
+        self.participants
+            .show_status(format!("Score {}, {left}", self.score));
+    }
+
+    fn build_wall(&mut self, layer: &mut ChunkLayer) -> Result<(), BuildGameError> {
+        let opp_dir = opposite_dir(&self.dir);
+        let wall_posistions = self.wall_blocks.concat();
+        let button_positions = self.button_blocks.concat();
+
+        for pos in &wall_posistions {
+            if layer.block(*pos)
+                != Some(BlockRef {
+                    state: BlockState::AIR,
+                    nbt: None,
+                })
+            {
+                return Err(BuildGameError::BlocksInTheWay);
+            }
+        }
+        for pos in &button_positions {
+            if layer.block(*pos)
+                != Some(BlockRef {
+                    state: BlockState::AIR,
+                    nbt: None,
+                })
+            {
+                return Err(BuildGameError::BlocksInTheWay);
+            }
+        }
+        for pos in &wall_posistions {
+            layer.set_block(*pos, BlockState::STONE);
+        }
+        for pos in &button_positions {
+            layer.set_block(*pos, button_block(&opp_dir));
+        }
+        self.is_built = true;
+        Ok(())
+    }
+
+    /// the floor of the cube replaces the ground the player stands on, everything else needs room
+    fn build_cube(&mut self, layer: &mut ChunkLayer) -> Result<(), BuildGameError> {
+        let cells: Vec<(BlockPos, BlockPos)> = self
+            .wall_blocks
+            .concat()
+            .into_iter()
+            .zip(self.button_blocks.concat())
+            .collect();
+        let is_air = |pos: &BlockPos| {
+            layer
+                .block(*pos)
+                .map_or(true, |b| b.state == BlockState::AIR)
+        };
+        for (wall, button) in &cells {
+            let is_floor = direction_between(wall, button) == Direction::Up;
+            if (!is_floor && !is_air(wall)) || !is_air(button) {
+                return Err(BuildGameError::BlocksInTheWay);
+            }
+        }
+        for (wall, button) in &cells {
+            let facing = direction_between(wall, button);
+            if facing == Direction::Up {
+                let ground = layer.block(*wall).map_or(BlockState::AIR, |b| b.state);
+                self.replaced_floor.push((*wall, ground));
+            }
+            layer.set_block(*wall, BlockState::STONE);
+            layer.set_block(*button, button_block(&facing));
+        }
+        self.is_built = true;
+        Ok(())
+    }
+
+    /// the tiles replace the ground, there has to be room to walk on them
+    fn build_floor(&mut self, layer: &mut ChunkLayer) -> Result<(), BuildGameError> {
+        for pos in self.wall_blocks.iter().flatten() {
+            for above in [pos.offset(0, 1, 0), pos.offset(0, 2, 0)] {
+                if layer
+                    .block(above)
+                    .is_some_and(|b| b.state != BlockState::AIR)
+                {
+                    return Err(BuildGameError::BlocksInTheWay);
+                }
+            }
+        }
+        for pos in self.wall_blocks.iter().flatten() {
+            let ground = layer.block(*pos).map_or(BlockState::AIR, |b| b.state);
+            self.replaced_floor.push((*pos, ground));
+            layer.set_block(*pos, BlockState::STONE);
+        }
+        self.is_built = true;
+        Ok(())
+    }
 }
 
-impl<const DIM: usize> CustomGame for RepeatSequenceGame<DIM> {
-    fn reset(&self, layer: &mut ChunkLayer, pgsql: &mut PostgresWrapper) {
-        for block in self.wall_blocks.flatten() {
+impl CustomGame for RepeatSequenceGame {
+    fn reset(&self, layer: &mut ChunkLayer) {
+        for block in self.wall_blocks.iter().flatten() {
             layer.set_block(*block, BlockState::AIR);
         }
-        for block in self.button_blocks.flatten() {
+        for block in self.button_blocks.iter().flatten() {
             layer.set_block(*block, BlockState::AIR);
         }
+        for (pos, ground) in &self.replaced_floor {
+            layer.set_block(*pos, *ground);
+        }
+    }
 
-        pgsql.insert_rsg(DIM as i32, self.sequence.len() as i32, self.player.1);
+    fn save(&self, pgsql: &mut PostgresWrapper) {
+        pgsql.insert_rsg(RsgEntry {
+            width: self.width as i32,
+            height: self.height as i32,
+            streak: self.sequence.len() as i32,
+            speed: self.speed.name(),
+            mode: self.mode.name(),
+            display: self.display.name(),
+            variant: self.variant.name(),
+            layout: self.layout.name(),
+            missed_clicks: self.missed_clicks as i32,
+            total_time_ms: (self.total_ticks * 50) as i32,
+            score: self.score as i32,
+            correct_inputs: self.correct_inputs as i32,
+            average_reaction_ms: self.reaction_times_ms().map(|(average, _)| average as i32),
+            best_reaction_ms: self.reaction_times_ms().map(|(_, best)| best as i32),
+            reaction_times_ms: self
+                .reaction_ticks
+                .iter()
+                .map(|t| (t * 50) as i32)
+                .collect(),
+            seed: self.seed,
+            team_size: self.team.len() as i32,
+            uuid: self.participants.owner().1,
+        });
     }
 
     fn should_despawn(&self) -> bool {
         self.should_despawn
     }
 
+    /// a repeat sequence game goes on until the player fails, so it is never completed
+    fn progress(&self) -> f32 {
+        0.0
+    }
+
+    fn is_over(&self) -> bool {
+        self.should_despawn
+    }
+
+    fn is_won(&self) -> bool {
+        false
+    }
+
     fn tick(&mut self, layer: &mut ChunkLayer) {
         self.ticks += 1;
+        self.total_ticks += 1;
+        let step_ticks = self.speed.step_ticks(self.sequence.len());
+        self.apply_scheduled_blocks(false, layer);
+        if let Some(limit) = self.mode.time_limit() {
+            if self.total_ticks >= limit {
+                if !self.should_despawn {
+                    self.participants.show_status("Time is up!");
+                    self.participants.announce(format!(
+                        "Time is up! {} correct steps, score {}",
+                        self.correct_inputs, self.score
+                    ));
+                    self.finish();
+                }
+                return;
+            } else if self.total_ticks % 10 == 0 {
+                self.show_status();
+            }
+        }
+        if let Some((pos, since)) = self.standing_on {
+            if self.total_ticks - since == STEP_DWELL_TICKS {
+                self.input(&pos, since, layer);
+            }
+        }
 
         if self.state == GameState::Idle {
-            if self.ticks > 20 {
+            if self.ticks > step_ticks {
+                // the click feedback must not mix with the next sequence
+                self.apply_scheduled_blocks(true, layer);
                 self.state = GameState::Displaying;
                 self.ticks = 0;
                 self.generate_sequence();
             }
         } else if self.state == GameState::Displaying {
-            let display_step = self.ticks / 20;
-            if self.ticks % 20 == 1 {
+            let display_step = self.ticks / step_ticks;
+            if self.ticks % step_ticks == 1 {
                 //reset previous displayed block
                 if display_step != 0 {
                     layer.set_block(
@@ -182,110 +1024,108 @@ impl<const DIM: usize> CustomGame for RepeatSequenceGame<DIM> {
                 if display_step >= self.sequence.len() {
                     self.state = GameState::WaitForInput;
                     self.ticks = 0;
+                    self.input_ready = self.total_ticks;
                     return;
                 }
                 //place the block to display
-                layer.set_block(
-                    self.sequence.get(display_step).unwrap().1,
-                    BlockState::RED_CONCRETE,
-                );
+                self.show_step(self.sequence.get(display_step).unwrap(), layer);
             }
         } else if self.state == GameState::WaitForInput {
-            if self.ticks > (20 * 10) {
-                self.should_despawn = true;
+            if self.ticks > self.mode.input_timeout() {
+                self.finish();
             }
         }
     }
-    fn click_left(&mut self, _click_pos: &BlockPos, _player: Entity, _layer: &mut ChunkLayer) {}
+    fn click_left(
+        &mut self,
+        _click_pos: &BlockPos,
+        _player: Entity,
+        _layer: &mut ChunkLayer,
+    ) -> bool {
+        return false;
+    }
 
-    fn click_right(&mut self, click_pos: &BlockPos, player: Entity, layer: &mut ChunkLayer) {
-        if player == self.player.0
-            && self.state == GameState::WaitForInput
-            && self.button_blocks.flatten().contains(&click_pos)
-        {
-            if self.sequence.get(self.input_progres).unwrap().0 == *click_pos {
-                sound(layer, Sound::BlockNoteBlockBanjo, click_pos);
-                self.input_progres += 1;
-                if self.input_progres == self.sequence.len() {
-                    self.state = GameState::Idle;
-                    self.input_progres = 0;
-                    sound(layer, Sound::BlockBeehiveEnter, click_pos);
-                }
-            } else {
-                sound(layer, Sound::EntityCreeperDeath, click_pos);
-                self.missed_clicks += 1;
+    fn click_right(
+        &mut self,
+        click_pos: &BlockPos,
+        player: Entity,
+        layer: &mut ChunkLayer,
+    ) -> bool {
+        if self.layout == SequenceLayout::Floor {
+            return false;
+        }
+        self.join_team(player);
+        return self.input(click_pos, self.total_ticks, layer);
+    }
 
-                if self.missed_clicks >= 3 {
-                    self.should_despawn = true;
-                }
-            }
+    fn step_on(&mut self, pos: &BlockPos, player: Entity, _layer: &mut ChunkLayer) -> bool {
+        if self.layout != SequenceLayout::Floor {
+            return false;
         }
+        self.join_team(player);
+        self.standing_on = Some((*pos, self.total_ticks));
+        return true;
     }
 
     fn build_blocks(&mut self, layer: &mut ChunkLayer) -> Result<(), BuildGameError> {
-        let opp_dir = opposite_dir(&self.dir);
-        let wall_posistions = self.wall_blocks.flatten();
-        let button_positions = self.button_blocks.flatten();
-
-        for pos in wall_posistions {
-            if layer.block(*pos)
-                != Some(BlockRef {
-                    state: BlockState::AIR,
-                    nbt: None,
-                })
-            {
-                return Err(BuildGameError::BlocksInTheWay);
-            }
-        }
-        for pos in button_positions {
-            if layer.block(*pos)
-                != Some(BlockRef {
-                    state: BlockState::AIR,
-                    nbt: None,
-                })
-            {
-                return Err(BuildGameError::BlocksInTheWay);
-            }
-        }
-        for pos in wall_posistions {
-            layer.set_block(*pos, BlockState::STONE);
+        match self.layout {
+            SequenceLayout::Wall => self.build_wall(layer),
+            SequenceLayout::Floor => self.build_floor(layer),
+            SequenceLayout::Cube => self.build_cube(layer),
         }
-        let button_dir: PropValue;
+    }
 
-        if self.dir == Direction::North || self.dir == Direction::South {
-            button_dir = dir_to_prop_value(&self.dir);
-        } else {
-            button_dir = dir_to_prop_value(&opp_dir);
-        }
+    fn seed(&self) -> GameSeed {
+        self.seed
+    }
 
-        for pos in button_positions {
-            layer.set_block(
-                *pos,
-                BlockState::OAK_BUTTON
-                    .set(PropName::Face, PropValue::Wall)
-                    .set(PropName::Facing, button_dir),
-            );
-        }
-        self.is_built = true;
-        Ok(())
+    fn origin(&self) -> BlockPos {
+        self.wall_blocks[0][0]
     }
 
-    fn get_player(&self) -> (Entity, UniqueId) {
-        self.player
+    fn participants(&self) -> &Participants {
+        &self.participants
+    }
+    fn participants_mut(&mut self) -> &mut Participants {
+        &mut self.participants
     }
 }
 
 fn dir_to_prop_value(dir: &Direction) -> PropValue {
     match dir {
         Direction::West => PropValue::West,
-        Direction::South => PropValue::North,
-        Direction::North => PropValue::South,
-        Direction::Up => PropValue::Down,
-        Direction::Down => PropValue::Up,
+        Direction::South => PropValue::South,
+        Direction::North => PropValue::North,
+        Direction::Up => PropValue::Up,
+        Direction::Down => PropValue::Down,
         Direction::East => PropValue::East,
     }
 }
 
+/// a button pointing in the direction, it sits on the block on the opposite side
+fn button_block(facing: &Direction) -> BlockState {
+    let button = BlockState::OAK_BUTTON;
+    match facing {
+        Direction::Up => button.set(PropName::Face, PropValue::Floor),
+        Direction::Down => button.set(PropName::Face, PropValue::Ceiling),
+        _ => button
+            .set(PropName::Face, PropValue::Wall)
+            .set(PropName::Facing, dir_to_prop_value(facing)),
+    }
+}
+
+/// the direction from a block to the one next to it
+fn direction_between(from: &BlockPos, to: &BlockPos) -> Direction {
+    match (to.x - from.x, to.y - from.y, to.z - from.z) {
+        (1, 0, 0) => Direction::East,
+        (-1, 0, 0) => Direction::West,
+        (0, 1, 0) => Direction::Up,
+        (0, -1, 0) => Direction::Down,
+        (0, 0, 1) => Direction::South,
+        _ => Direction::North,
+    }
+}
+
 fn opposite_dir(dir: &Direction) -> Direction {
     match dir {
         Direction::South => Direction::North,
@@ -298,12 +1138,16 @@ fn opposite_dir(dir: &Direction) -> Direction {
 }
 
 fn sound(layer: &mut ChunkLayer, sound: Sound, loc: &BlockPos) {
+    play_note(layer, sound, loc, 1.0);
+}
+
+fn play_note(layer: &mut ChunkLayer, sound: Sound, loc: &BlockPos, pitch: f32) {
     layer.play_sound(
         sound,
         SoundCategory::Ambient,
         DVec3::new(loc.x.into(), loc.y.into(), loc.z.into()),
         20.0,
-        1.0,
+        pitch,
     );
 }
 
@@ -0,0 +1,126 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// seeds are limited to 40 bits, so their codes stay short enough to share in chat
+const SEED_BITS: u32 = 40;
+const SEED_CODE_LEN: usize = 8;
+/// crockford base32, leaves out letters which are easily confused with each other
+const CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The seed a game generates its board or sequence from.
+/// Games with the same seed and the same inputs play out exactly the same
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameSeed {
+    pub value: u64,
+    pub daily: bool,
+}
+
+impl GameSeed {
+    pub fn random() -> GameSeed {
+        GameSeed {
+            value: rand::thread_rng().gen_range(0..1 << SEED_BITS),
+            daily: false,
+        }
+    }
+
+    /// the seed of todays daily challenge, its the same for every player until midnight (UTC)
+    pub fn daily() -> GameSeed {
+        let days = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            / (60 * 60 * 24);
+        GameSeed {
+            value: splitmix64(days) >> (64 - SEED_BITS),
+            daily: true,
+        }
+    }
+
+    pub fn from_value(value: u64) -> GameSeed {
+        GameSeed {
+            value,
+            daily: false,
+        }
+    }
+
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.value)
+    }
+
+    pub fn code(&self) -> String {
+        (0..SEED_CODE_LEN)
+            .rev()
+            .map(|i| CODE_ALPHABET[(self.value >> (i * 5)) as usize & 31] as char)
+            .collect()
+    }
+
+    /// parses a code made by [GameSeed::code], ignoring case
+    pub fn from_code(code: &str) -> Option<GameSeed> {
+        if code.len() != SEED_CODE_LEN {
+            return None;
+        }
+        let mut value = 0;
+        for c in code.chars() {
+            value = (value << 5) | code_char_value(c)?;
+        }
+        Some(GameSeed::from_value(value))
+    }
+}
+
+fn code_char_value(c: char) -> Option<u64> {
+    let c = match c.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        c => c,
+    };
+    CODE_ALPHABET
+        .iter()
+        .position(|a| *a as char == c)
+        .map(|v| v as u64)
+}
+
+/// spreads consecutive days over the whole seed range
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_round_trip() {
+        for value in [0, 1, 0x12_3456_789a, (1 << SEED_BITS) - 1] {
+            let seed = GameSeed::from_value(value);
+            assert_eq!(seed.code().len(), SEED_CODE_LEN);
+            assert_eq!(GameSeed::from_code(&seed.code()), Some(seed));
+            assert_eq!(GameSeed::from_code(&seed.code().to_lowercase()), Some(seed));
+        }
+    }
+
+    #[test]
+    fn confusable_letters() {
+        assert_eq!(
+            GameSeed::from_code("OoOoOoOo"),
+            Some(GameSeed::from_value(0))
+        );
+        assert_eq!(
+            GameSeed::from_code("0000000I"),
+            Some(GameSeed::from_value(1))
+        );
+        assert_eq!(
+            GameSeed::from_code("0000000l"),
+            Some(GameSeed::from_value(1))
+        );
+    }
+
+    #[test]
+    fn invalid_codes() {
+        assert_eq!(GameSeed::from_code("0000000U"), None);
+        assert_eq!(GameSeed::from_code("0000000"), None);
+        assert_eq!(GameSeed::from_code("000000000"), None);
+    }
+}